@@ -1,5 +1,7 @@
 use sc_service::ChainType;
-use solochain_template_runtime::{AccountId, Signature, WASM_BINARY};
+use solochain_template_runtime::{
+	AccountId, Balance, BlockNumber, SessionKeys, Signature, DAYS, UNIT, WASM_BINARY,
+};
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_consensus_grandpa::AuthorityId as GrandpaId;
 use sp_core::{sr25519, Pair, Public};
@@ -28,9 +30,27 @@ where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
-/// Generate an Aura authority key.
-pub fn authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
-	(get_from_seed::<AuraId>(s), get_from_seed::<GrandpaId>(s))
+/// Generate a validator account with its Aura and Grandpa authority keys.
+pub fn authority_keys_from_seed(s: &str) -> (AccountId, AuraId, GrandpaId) {
+	(
+		get_account_id_from_seed::<sr25519::Public>(s),
+		get_from_seed::<AuraId>(s),
+		get_from_seed::<GrandpaId>(s),
+	)
+}
+
+fn session_keys(aura: AuraId, grandpa: GrandpaId) -> SessionKeys {
+	SessionKeys { aura, grandpa }
+}
+
+/// A genesis allocation to a team member or investor, of which `liquid` can be spent at once and
+/// the rest unlocks linearly over `length` blocks starting at block `begin`.
+pub struct VestingAllocation {
+	pub who: AccountId,
+	pub amount: Balance,
+	pub liquid: Balance,
+	pub begin: BlockNumber,
+	pub length: BlockNumber,
 }
 
 pub fn development_config() -> Result<ChainSpec, String> {
@@ -46,6 +66,8 @@ pub fn development_config() -> Result<ChainSpec, String> {
 		vec![authority_keys_from_seed("Alice")],
 		// Sudo account
 		get_account_id_from_seed::<sr25519::Public>("Alice"),
+		// Council members
+		vec![get_account_id_from_seed::<sr25519::Public>("Alice")],
 		// Pre-funded accounts
 		vec![
 			get_account_id_from_seed::<sr25519::Public>("Alice"),
@@ -53,6 +75,14 @@ pub fn development_config() -> Result<ChainSpec, String> {
 			get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
 			get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
 		],
+		// Vesting allocations
+		vec![VestingAllocation {
+			who: get_account_id_from_seed::<sr25519::Public>("Dave"),
+			amount: 1_000_000 * UNIT,
+			liquid: 100_000 * UNIT,
+			begin: 0,
+			length: 365 * DAYS,
+		}],
 		true,
 	))
 	.build())
@@ -71,6 +101,12 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 		vec![authority_keys_from_seed("Alice"), authority_keys_from_seed("Bob")],
 		// Sudo account
 		get_account_id_from_seed::<sr25519::Public>("Alice"),
+		// Council members
+		vec![
+			get_account_id_from_seed::<sr25519::Public>("Alice"),
+			get_account_id_from_seed::<sr25519::Public>("Bob"),
+			get_account_id_from_seed::<sr25519::Public>("Charlie"),
+		],
 		// Pre-funded accounts
 		vec![
 			get_account_id_from_seed::<sr25519::Public>("Alice"),
@@ -86,6 +122,8 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 			get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
 			get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
 		],
+		// Vesting allocations
+		vec![],
 		true,
 	))
 	.build())
@@ -93,25 +131,47 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
-	initial_authorities: Vec<(AuraId, GrandpaId)>,
+	initial_authorities: Vec<(AccountId, AuraId, GrandpaId)>,
 	root_key: AccountId,
+	council_members: Vec<AccountId>,
 	endowed_accounts: Vec<AccountId>,
+	vesting_allocations: Vec<VestingAllocation>,
 	_enable_println: bool,
 ) -> serde_json::Value {
 	serde_json::json!({
 		"balances": {
-			// Configure endowed accounts with initial balance of 1 << 60.
-			"balances": endowed_accounts.iter().cloned().map(|k| (k, 1u64 << 60)).collect::<Vec<_>>(),
+			// Configure endowed accounts with initial balance of 1 << 60, and vesting accounts
+			// with their allocation. An account can't be both.
+			"balances": endowed_accounts
+				.iter()
+				.cloned()
+				.map(|k| (k, 1u128 << 60))
+				.chain(vesting_allocations.iter().map(|a| (a.who.clone(), a.amount)))
+				.collect::<Vec<_>>(),
+		},
+		"vesting": {
+			"vesting": vesting_allocations
+				.iter()
+				.map(|a| (a.who.clone(), a.begin, a.length, a.liquid))
+				.collect::<Vec<_>>(),
 		},
-		"aura": {
-			"authorities": initial_authorities.iter().map(|x| (x.0.clone())).collect::<Vec<_>>(),
+		"validatorSet": {
+			"initialValidators": initial_authorities.iter().map(|x| x.0.clone()).collect::<Vec<_>>(),
 		},
-		"grandpa": {
-			"authorities": initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect::<Vec<_>>(),
+		"session": {
+			// Aura and Grandpa authorities are set from the session keys of the validators.
+			"keys": initial_authorities
+				.iter()
+				.map(|x| (x.0.clone(), x.0.clone(), session_keys(x.1.clone(), x.2.clone())))
+				.collect::<Vec<_>>(),
 		},
 		"sudo": {
-			// Assign network admin rights.
+			// Assign network admin rights, until they are given up with `sudo.removeKey` once
+			// the council can reach root through referenda.
 			"key": Some(root_key),
 		},
+		"council": {
+			"members": council_members,
+		},
 	})
 }
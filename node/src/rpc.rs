@@ -44,6 +44,9 @@ where
 	module.merge(System::new(client.clone(), pool).into_rpc())?;
 	module.merge(TransactionPayment::new(client).into_rpc())?;
 
+	// Contracts have no dedicated RPC anymore: `call`, `instantiate`, `upload_code` and
+	// `get_storage` are dry-run through `state_call` on the runtime's `ContractsApi`.
+
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
 	// to call into the runtime.
@@ -0,0 +1,45 @@
+//! Runtime API definition for the collectables pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use codec::Codec;
+
+/// A page of kitties with their rarity score, and the kitty to continue after.
+pub type RarityPage = (Vec<([u8; 32], u128)>, Option<[u8; 32]>);
+
+sp_api::decl_runtime_apis! {
+	/// Queries over the kitties of the collectables pallet.
+	#[api_version(2)]
+	pub trait CollectablesApi<AccountId, Balance, BlockNumber>
+	where
+		AccountId: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// The previous owners of a kitty, oldest first, as
+		/// `(previous_owner, block_number, sale_price)`.
+		fn ownership_history(kitty_id: [u8; 32]) -> Vec<(AccountId, BlockNumber, Option<Balance>)>;
+
+		/// The kitties among at most `limit` kitties following `start_after`, with their rarity
+		/// score, rarest first, and the kitty to continue after. A page holds at most 256 kitties.
+		#[api_version(2)]
+		fn rarest_kitties(
+			start_after: Option<[u8; 32]>,
+			limit: u32,
+		) -> RarityPage;
+
+		/// The number of owners, among at most `limit` owners following `start_after`, whose
+		/// kitties have a higher summed rarity score than those of `owner`, and the owner to
+		/// continue after. The rank of `owner` is one more than the sum over all pages. `None` if
+		/// `owner` has no kitty. A page holds at most 256 owners.
+		#[api_version(2)]
+		fn owners_rarer_than(
+			owner: AccountId,
+			start_after: Option<AccountId>,
+			limit: u32,
+		) -> Option<(u32, Option<AccountId>)>;
+	}
+}
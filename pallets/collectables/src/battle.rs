@@ -0,0 +1,208 @@
+use super::*;
+use alloc::vec::Vec;
+use frame::prelude::*;
+use frame::primitives::BlakeTwo256;
+use frame::traits::tokens::Preservation;
+use frame::traits::Hash;
+use frame::traits::Saturating;
+use frame::traits::Zero;
+
+/// A tournament entrant: the kitty and the account that entered it.
+type Entrant<T> = ([u8; 32], <T as frame_system::Config>::AccountId);
+
+impl<T: Config> Pallet<T> {
+	/// The battle power of a kitty: the attack, defense and speed encoded in its DNA, plus a
+	/// bonus for every level.
+	pub fn battle_power(kitty: &Kitty<T>) -> u32 {
+		let stats = kitty.dna[1] as u32 + kitty.dna[2] as u32 + kitty.dna[3] as u32;
+		stats.saturating_add(kitty.level.saturating_mul(10)).max(1)
+	}
+
+	pub fn do_create_tournament(
+		organizer: T::AccountId,
+		entry_fee: BalanceOf<T>,
+		size: u32,
+	) -> DispatchResult {
+		ensure!(
+			size >= 2 && size.is_power_of_two() && size <= T::MaxTournamentSize::get(),
+			Error::<T>::InvalidTournamentSize
+		);
+		let tournament_id = NextTournamentId::<T>::get();
+		let next_id = tournament_id.checked_add(1).ok_or(Error::<T>::TooManyTournaments)?;
+
+		let tournament = Tournament {
+			organizer: organizer.clone(),
+			entry_fee,
+			size,
+			entrants: BoundedVec::new(),
+			prize_pool: Zero::zero(),
+			seed_block: None,
+		};
+		let now = frame_system::Pallet::<T>::block_number();
+		Tournaments::<T>::insert(tournament_id, tournament);
+		TournamentDeadlines::<T>::insert(
+			tournament_id,
+			now.saturating_add(T::RegistrationPeriod::get()),
+		);
+		NextTournamentId::<T>::put(next_id);
+
+		Self::deposit_event(Event::<T>::TournamentCreated {
+			tournament_id,
+			organizer,
+			entry_fee,
+			size,
+		});
+		Ok(())
+	}
+
+	/// Registers a kitty used by `player` into the bracket, escrowing the entry fee. The kitty
+	/// cannot change hands until the tournament is resolved or cancelled.
+	pub fn do_join_tournament(
+		player: T::AccountId,
+		tournament_id: u32,
+		kitty_id: [u8; 32],
+	) -> DispatchResult {
+		let mut tournament =
+			Tournaments::<T>::get(tournament_id).ok_or(Error::<T>::NoTournament)?;
+		ensure!(tournament.seed_block.is_none(), Error::<T>::TournamentFull);
+		let now = frame_system::Pallet::<T>::block_number();
+		if let Some(deadline) = TournamentDeadlines::<T>::get(tournament_id) {
+			ensure!(now < deadline, Error::<T>::RegistrationClosed);
+		}
+		let user = Self::user_of(kitty_id).ok_or(Error::<T>::NoKitty)?;
+		ensure!(user == player, Error::<T>::NotOwner);
+		// A borrower may enter the kitty it borrowed, so an active lending is the only lock
+		// allowed here.
+		match Lendings::<T>::get(kitty_id) {
+			Some(Lending { expires: Some(_), .. }) => {
+				Self::ensure_unlocked_except_lending(kitty_id)?
+			},
+			_ => Self::ensure_unlocked(kitty_id)?,
+		}
+
+		tournament
+			.entrants
+			.try_push((kitty_id, player.clone()))
+			.map_err(|_| Error::<T>::TournamentFull)?;
+		if !tournament.entry_fee.is_zero() {
+			T::NativeBalance::transfer(
+				&player,
+				&Self::account_id(),
+				tournament.entry_fee,
+				Preservation::Preserve,
+			)?;
+			tournament.prize_pool.saturating_accrue(tournament.entry_fee);
+		}
+		if tournament.entrants.len() as u32 >= tournament.size {
+			tournament.seed_block = Some(now.saturating_add(T::TournamentSeedDelay::get()));
+		}
+		TournamentEntries::<T>::insert(kitty_id, tournament_id);
+		Tournaments::<T>::insert(tournament_id, tournament);
+
+		Self::deposit_event(Event::<T>::TournamentJoined { tournament_id, player, kitty_id });
+		Ok(())
+	}
+
+	/// Refunds every entrant of a tournament whose bracket is not full yet. The organizer can
+	/// cancel at any time, and anyone else once registration has closed.
+	pub fn do_cancel_tournament(who: T::AccountId, tournament_id: u32) -> DispatchResult {
+		let tournament = Tournaments::<T>::get(tournament_id).ok_or(Error::<T>::NoTournament)?;
+		let now = frame_system::Pallet::<T>::block_number();
+		let closed =
+			TournamentDeadlines::<T>::get(tournament_id).is_some_and(|deadline| now >= deadline);
+		ensure!(tournament.organizer == who || closed, Error::<T>::NotOwner);
+		ensure!(tournament.seed_block.is_none(), Error::<T>::TournamentFull);
+
+		Self::refund_tournament(tournament_id, tournament)
+	}
+
+	/// Returns the entry fee to every entrant, releases their kitties and removes the
+	/// tournament.
+	fn refund_tournament(tournament_id: u32, tournament: Tournament<T>) -> DispatchResult {
+		for (kitty_id, player) in tournament.entrants {
+			TournamentEntries::<T>::remove(kitty_id);
+			if !tournament.entry_fee.is_zero() {
+				T::NativeBalance::transfer(
+					&Self::account_id(),
+					&player,
+					tournament.entry_fee,
+					Preservation::Expendable,
+				)?;
+			}
+		}
+		Tournaments::<T>::remove(tournament_id);
+		TournamentDeadlines::<T>::remove(tournament_id);
+
+		Self::deposit_event(Event::<T>::TournamentCancelled { tournament_id });
+		Ok(())
+	}
+
+	/// Plays every round of a full bracket. Matches are seeded by the hash of a block that did
+	/// not exist when the bracket filled up, and every winner gains experience. Once that hash
+	/// is no longer kept by the chain, the tournament is cancelled and every entrant refunded.
+	pub fn do_resolve_tournament(tournament_id: u32) -> DispatchResult {
+		let tournament = Tournaments::<T>::get(tournament_id).ok_or(Error::<T>::NoTournament)?;
+		let seed_block = tournament.seed_block.ok_or(Error::<T>::TournamentNotFull)?;
+		let now = frame_system::Pallet::<T>::block_number();
+		ensure!(now > seed_block, Error::<T>::TournamentNotReady);
+		let seed = frame_system::Pallet::<T>::block_hash(seed_block);
+		if seed == Default::default() {
+			return Self::refund_tournament(tournament_id, tournament)
+		}
+
+		let mut round: Vec<Entrant<T>> = tournament.entrants.to_vec();
+		while round.len() > 1 {
+			let mut next_round = Vec::with_capacity(round.len() / 2);
+			for pair in round.chunks_exact(2) {
+				let (winner, loser) = Self::play_match(&seed, &pair[0], &pair[1]);
+				Self::add_xp(winner.0, T::XpPerWin::get())?;
+				Self::deposit_event(Event::<T>::MatchResolved {
+					tournament_id,
+					winner: winner.0,
+					loser: loser.0,
+				});
+				next_round.push(winner.clone());
+			}
+			round = next_round;
+		}
+		let (kitty_id, player) = round.pop().ok_or(Error::<T>::TournamentNotFull)?;
+
+		for (entrant, _) in tournament.entrants.iter() {
+			TournamentEntries::<T>::remove(entrant);
+		}
+		Tournaments::<T>::remove(tournament_id);
+		TournamentDeadlines::<T>::remove(tournament_id);
+		let prize = tournament.prize_pool;
+		if !prize.is_zero() {
+			T::NativeBalance::transfer(
+				&Self::account_id(),
+				&player,
+				prize,
+				Preservation::Expendable,
+			)?;
+		}
+
+		Self::deposit_event(Event::<T>::TournamentWon { tournament_id, player, kitty_id, prize });
+		Ok(())
+	}
+
+	/// Picks the winner of a match with odds proportional to the battle power of each kitty.
+	fn play_match<'a, S: Encode>(
+		seed: &S,
+		a: &'a Entrant<T>,
+		b: &'a Entrant<T>,
+	) -> (&'a Entrant<T>, &'a Entrant<T>) {
+		let power = |kitty_id: [u8; 32]| {
+			Kitties::<T>::get(kitty_id).map(|kitty| Self::battle_power(&kitty)).unwrap_or(1)
+		};
+		let (power_a, power_b) = (power(a.0), power(b.0));
+
+		let roll_hash: [u8; 32] = BlakeTwo256::hash_of(&(seed, a.0, b.0)).into();
+		let roll = u32::from_le_bytes([roll_hash[0], roll_hash[1], roll_hash[2], roll_hash[3]]);
+		if roll % power_a.saturating_add(power_b) < power_a {
+			(a, b)
+		} else {
+			(b, a)
+		}
+	}
+}
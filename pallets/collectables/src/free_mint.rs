@@ -0,0 +1,136 @@
+use super::*;
+use alloc::vec::Vec;
+use core::fmt;
+use frame::deps::frame_support::traits::IsSubType;
+use frame::deps::sp_runtime::traits::{
+	DispatchInfoOf, PostDispatchInfoOf, SignedExtension, SignedExtensionMetadata,
+};
+use frame::traits::Hash;
+
+/// Makes the first successful `create_kitty` of every account feeless by skipping the fee
+/// payment extension `S` it wraps. A free mint that fails can be retried for free.
+///
+/// The transaction pool holds at most `MaxFreeMintsPerBlock` free mints, each account mapping to
+/// one of as many slots, and drops them after `FreeMintLongevity` blocks. At most
+/// `MaxFreeMintsPerBlock` of them make it into a block, the rest waiting for the next one.
+///
+/// The sender must still exist, e.g. by holding the existential deposit or a sufficient asset,
+/// as `CheckNonce` rejects unknown accounts. Accepting them would let anyone create accounts,
+/// and their storage, without a deposit, so the first mint is free for funded accounts only.
+/// Later mints, and any other call, pay fees through `S` as usual.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct FreeFirstMint<T, S>(pub S, core::marker::PhantomData<T>);
+
+impl<T, S> From<S> for FreeFirstMint<T, S> {
+	fn from(payment: S) -> Self {
+		Self(payment, Default::default())
+	}
+}
+
+impl<T, S: Encode> fmt::Debug for FreeFirstMint<T, S> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "FreeFirstMint<{:?}>", self.0.encode())
+	}
+}
+
+/// What [`FreeFirstMint`] passes from `pre_dispatch` to `post_dispatch`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum FreeMintPre<AccountId, P> {
+	/// A free mint by the account, marked as used once it succeeds.
+	Free(AccountId),
+	/// Any other transaction, paid through the wrapped extension.
+	Paid(P),
+}
+
+impl<T: Config, S> FreeFirstMint<T, S>
+where
+	<T as frame_system::Config>::RuntimeCall: IsSubType<Call<T>>,
+{
+	fn is_free_mint(who: &T::AccountId, call: &<T as frame_system::Config>::RuntimeCall) -> bool {
+		matches!(call.is_sub_type(), Some(Call::create_kitty {}))
+			&& T::MaxFreeMintsPerBlock::get() > 0
+			&& !FreeMintUsed::<T>::get(who)
+	}
+}
+
+impl<T: Config + Send + Sync, S> SignedExtension for FreeFirstMint<T, S>
+where
+	S: SignedExtension<AccountId = T::AccountId, Call = <T as frame_system::Config>::RuntimeCall>,
+	<T as frame_system::Config>::RuntimeCall: IsSubType<Call<T>>,
+{
+	// Wallets see the wrapped payment extension, which this one encodes exactly like.
+	const IDENTIFIER: &'static str = S::IDENTIFIER;
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Config>::RuntimeCall;
+	type AdditionalSigned = S::AdditionalSigned;
+	type Pre = FreeMintPre<T::AccountId, S::Pre>;
+
+	fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
+		self.0.additional_signed()
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		if Self::is_free_mint(who, call) {
+			// Free mints of accounts in the same slot replace each other in the pool.
+			let mut seed = [0u8; 4];
+			seed.copy_from_slice(&T::Hashing::hash_of(who).as_ref()[..4]);
+			let slot = u32::from_le_bytes(seed) % T::MaxFreeMintsPerBlock::get();
+			return ValidTransaction::with_tag_prefix("CollectablesFreeMint")
+				.and_provides(who)
+				.and_provides(slot)
+				.longevity(T::FreeMintLongevity::get())
+				.build()
+		}
+		self.0.validate(who, call, info, len)
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		if Self::is_free_mint(who, call) {
+			let minted = FreeMintsInBlock::<T>::get();
+			if minted >= T::MaxFreeMintsPerBlock::get() {
+				return Err(InvalidTransaction::ExhaustsResources.into())
+			}
+			FreeMintsInBlock::<T>::put(minted + 1);
+			return Ok(FreeMintPre::Free(who.clone()))
+		}
+		Ok(FreeMintPre::Paid(self.0.pre_dispatch(who, call, info, len)?))
+	}
+
+	fn post_dispatch(
+		pre: Option<Self::Pre>,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		match pre {
+			Some(FreeMintPre::Free(who)) => {
+				if result.is_ok() {
+					FreeMintUsed::<T>::insert(who, true);
+				}
+				Ok(())
+			},
+			Some(FreeMintPre::Paid(pre)) => {
+				S::post_dispatch(Some(pre), info, post_info, len, result)
+			},
+			None => S::post_dispatch(None, info, post_info, len, result),
+		}
+	}
+
+	fn metadata() -> Vec<SignedExtensionMetadata> {
+		S::metadata()
+	}
+}
@@ -1,9 +1,21 @@
 use super::*;
+use alloc::vec::Vec;
+use frame::deps::sp_runtime::SaturatedConversion;
 use frame::prelude::*;
 use frame::primitives::BlakeTwo256;
+use frame::traits::fungibles::Inspect as _;
+use frame::traits::tokens::DepositConsequence;
+use frame::traits::tokens::Fortitude;
 use frame::traits::tokens::Preservation;
+use frame::traits::tokens::Provenance;
+use frame::traits::AccountIdConversion;
 use frame::traits::Hash;
+use frame::traits::One;
+use frame::traits::Saturating;
+use frame::traits::Zero;
 
+/// The scale applied to `RewardPerWeight` so that small rewards are not lost to rounding.
+const REWARD_PRECISION: u128 = 1_000_000_000_000;
 
 impl<T: Config> Pallet<T> {
 	
@@ -21,7 +33,7 @@ impl<T: Config> Pallet<T> {
 	}
 
 	pub fn mint(owner: T::AccountId, dna: [u8; 32]) -> DispatchResult {
-		let kitty = Kitty { dna, owner: owner.clone(), price: None };
+		let kitty = Kitty { dna, owner: owner.clone(), price: None, level: 1, xp: 0 };
 		
 		ensure!(!Kitties::<T>::contains_key(dna), Error::<T>::DuplicateKitty);
 
@@ -31,6 +43,7 @@ impl<T: Config> Pallet<T> {
 		KittiesOwned::<T>::try_append(&owner, dna).map_err(|_| Error::<T>::TooManyOwned)?;
 		Kitties::<T>::insert(dna, kitty);
 		CountForKitties::<T>::set(new_count);
+		Self::add_traits(&dna);
 
 		Self::deposit_event(Event::<T>::Created { owner });
 		Ok(())
@@ -38,9 +51,15 @@ impl<T: Config> Pallet<T> {
 
 	pub fn do_transfer(from: T::AccountId, to: T::AccountId, kitty_id: [u8; 32]) -> DispatchResult {
 		ensure!(from != to, Error::<T>::TransferToSelf);
+		Self::ensure_unlocked(kitty_id)?;
 		let mut kitty = Kitties::<T>::get(kitty_id).ok_or(Error::<T>::NoKitty)?;
 		ensure!(kitty.owner == from, Error::<T>::NotOwner);
+		Self::cancel_swaps_involving(kitty_id)?;
+		ListingExpiries::<T>::remove(kitty_id);
+		ListingAssets::<T>::remove(kitty_id);
+		DutchAuctions::<T>::remove(kitty_id);
 		kitty.owner = to.clone();
+		kitty.price = None;
 
 		let mut to_owned = KittiesOwned::<T>::get(&to);
 		to_owned.try_push(kitty_id).map_err(|_| Error::<T>::TooManyOwned)?;
@@ -54,22 +73,107 @@ impl<T: Config> Pallet<T> {
 		Kitties::<T>::insert(kitty_id, kitty);
 		KittiesOwned::<T>::insert(&to, to_owned);
 		KittiesOwned::<T>::insert(&from, from_owned);
+		Self::record_previous_owner(kitty_id, from.clone());
 
 		Self::deposit_event(Event::<T>::Transferred { from, to, kitty_id });
 		Ok(())
 	}
 
+	/// Destroys the kitty, clearing everything kept about it.
+	pub fn do_burn(owner: T::AccountId, kitty_id: [u8; 32]) -> DispatchResult {
+		let kitty = Kitties::<T>::get(kitty_id).ok_or(Error::<T>::NoKitty)?;
+		ensure!(kitty.owner == owner, Error::<T>::NotOwner);
+		Self::ensure_unlocked(kitty_id)?;
+		Self::cancel_swaps_involving(kitty_id)?;
+
+		KittiesOwned::<T>::mutate(&owner, |owned| owned.retain(|id| *id != kitty_id));
+		Kitties::<T>::remove(kitty_id);
+		CountForKitties::<T>::mutate(|count| count.saturating_dec());
+		ListingExpiries::<T>::remove(kitty_id);
+		ListingAssets::<T>::remove(kitty_id);
+		DutchAuctions::<T>::remove(kitty_id);
+		TrainingCooldowns::<T>::remove(kitty_id);
+		OwnershipHistory::<T>::remove(kitty_id);
+		Self::remove_traits(&kitty.dna);
+
+		Self::deposit_event(Event::<T>::Burned { owner, kitty_id });
+		Ok(())
+	}
+
+	/// Burns at least two kitties of `owner` and commits to mint a new one, one rarity tier
+	/// above the rarest input for every extra input consumed, and for every input at the
+	/// highest level. Like a mint commit, the DNA comes
+	/// from a block hash that does not exist yet; if the kitty cannot be minted when revealed,
+	/// the inputs are not given back.
+	pub fn do_fuse(
+		owner: T::AccountId,
+		kitty_ids: BoundedVec<[u8; 32], T::MaxFusionInputs>,
+	) -> DispatchResult {
+		ensure!(kitty_ids.len() >= 2, Error::<T>::InvalidFusion);
+		ensure!(!MintCommits::<T>::contains_key(&owner), Error::<T>::CommitPending);
+		let (mut rarest_tier, mut max_level_inputs) = (0, 0u8);
+		for (i, kitty_id) in kitty_ids.iter().enumerate() {
+			ensure!(!kitty_ids[..i].contains(kitty_id), Error::<T>::InvalidFusion);
+			let kitty = Kitties::<T>::get(kitty_id).ok_or(Error::<T>::NoKitty)?;
+			rarest_tier = rarest_tier.max(Self::rarity_tier(&kitty.dna));
+			if kitty.level >= T::MaxLevel::get() {
+				max_level_inputs.saturating_inc();
+			}
+		}
+		for kitty_id in kitty_ids.iter() {
+			Self::do_burn(owner.clone(), *kitty_id)?;
+		}
+
+		let tier = rarest_tier
+			.saturating_add((kitty_ids.len() - 1).saturated_into())
+			.saturating_add(max_level_inputs);
+		let target = Self::queue_mint_commit(&owner, Zero::zero(), tier)?;
+
+		Self::deposit_event(Event::<T>::Fused { owner, consumed: kitty_ids, target });
+		Ok(())
+	}
+
 	pub fn do_set_price(
 		caller: T::AccountId,
 		kitty_id: [u8; 32],
 		new_price: Option<BalanceOf<T>>,
+		expires_at: Option<BlockNumberFor<T>>,
+		asset_id: Option<AssetIdOf<T>>,
 	) -> DispatchResult {
 		let mut kitty = Kitties::<T>::get(kitty_id).ok_or(Error::<T>::NoKitty)?;
 		ensure!(kitty.owner == caller, Error::<T>::NotOwner);
+		if new_price.is_some() {
+			Self::ensure_unlocked(kitty_id)?;
+		}
+		// An expiry without a price has nothing to expire.
+		let expires_at = expires_at.filter(|_| new_price.is_some());
+		if let Some(expiry) = expires_at {
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(expiry > now, Error::<T>::InvalidExpiry);
+			ListingExpiryQueue::<T>::try_append(expiry, kitty_id)
+				.map_err(|_| Error::<T>::TooManyListingsExpiring)?;
+			ListingExpiries::<T>::insert(kitty_id, expiry);
+		} else {
+			ListingExpiries::<T>::remove(kitty_id);
+		}
+		let asset_id = asset_id.filter(|_| new_price.is_some());
+		if let Some(asset_id) = &asset_id {
+			ensure!(T::Assets::asset_exists(asset_id.clone()), Error::<T>::UnknownAsset);
+			ListingAssets::<T>::insert(kitty_id, asset_id);
+		} else {
+			ListingAssets::<T>::remove(kitty_id);
+		}
+		DutchAuctions::<T>::remove(kitty_id);
 		kitty.price = new_price;
 		Kitties::<T>::insert(kitty_id, kitty);
 
-		Self::deposit_event(Event::<T>::PriceSet { owner: caller, kitty_id, new_price });
+		Self::deposit_event(Event::<T>::PriceSet {
+			owner: caller,
+			kitty_id,
+			new_price,
+			expires_at,
+			asset_id,
+		});
 		Ok(())
 	}
 
@@ -79,13 +183,810 @@ impl<T: Config> Pallet<T> {
 		price: BalanceOf<T>,
 	) -> DispatchResult {
 		let kitty = Kitties::<T>::get(kitty_id).ok_or(Error::<T>::NoKitty)?;
-		let real_price = kitty.price.ok_or(Error::<T>::NotForSale)?;
+		Self::ensure_unlocked(kitty_id)?;
+		let now = frame_system::Pallet::<T>::block_number();
+		let real_price = match DutchAuctions::<T>::get(kitty_id) {
+			Some(auction) => {
+				Self::dutch_auction_price(&auction, now).ok_or(Error::<T>::NotForSale)?
+			},
+			None => kitty.price.ok_or(Error::<T>::NotForSale)?,
+		};
+		if let Some(expiry) = ListingExpiries::<T>::get(kitty_id) {
+			ensure!(now < expiry, Error::<T>::NotForSale);
+		}
 		ensure!(price >= real_price, Error::<T>::MaxPriceTooLow);
 
-		T::NativeBalance::transfer(&buyer, &kitty.owner, real_price, Preservation::Preserve)?;
+		let asset_id = ListingAssets::<T>::get(kitty_id);
+		let fee_destination = T::FeeDestination::get();
+		let mut fee = T::MarketplaceFee::get() * real_price;
+		// A fee too small to endow the fee destination goes to the seller instead.
+		if !Self::can_receive(&asset_id, &fee_destination, fee) {
+			fee = Zero::zero();
+		}
+		Self::pay(&asset_id, &buyer, &kitty.owner, real_price.saturating_sub(fee))?;
+		Self::pay(&asset_id, &buyer, &fee_destination, fee)?;
 		Self::do_transfer(kitty.owner, buyer.clone(), kitty_id)?;
+		Self::record_sale_price(kitty_id, real_price);
+
+		Self::deposit_event(Event::<T>::Sold { buyer, kitty_id, price: real_price, asset_id });
+		Ok(())
+	}
+
+	/// Pays `amount` in the given asset, or in the native token if there is none.
+	fn pay(
+		asset_id: &Option<AssetIdOf<T>>,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: BalanceOf<T>,
+	) -> DispatchResult {
+		if amount.is_zero() {
+			return Ok(())
+		}
+		match asset_id {
+			Some(asset_id) => {
+				<T::Assets as fungibles::Mutate<_>>::transfer(
+					asset_id.clone(),
+					from,
+					to,
+					amount,
+					Preservation::Preserve,
+				)?;
+			},
+			None => {
+				T::NativeBalance::transfer(from, to, amount, Preservation::Preserve)?;
+			},
+		}
+		Ok(())
+	}
+
+	/// Whether `who` can receive `amount` in the given asset, or in the native token if there is
+	/// none.
+	fn can_receive(
+		asset_id: &Option<AssetIdOf<T>>,
+		who: &T::AccountId,
+		amount: BalanceOf<T>,
+	) -> bool {
+		let consequence = match asset_id {
+			Some(asset_id) => {
+				T::Assets::can_deposit(asset_id.clone(), who, amount, Provenance::Extant)
+			},
+			None => T::NativeBalance::can_deposit(who, amount, Provenance::Extant),
+		};
+		consequence == DepositConsequence::Success
+	}
+
+	/// Puts the kitty up for a descending-price sale in the native token, replacing any fixed
+	/// price listing.
+	pub fn do_create_dutch_auction(
+		caller: T::AccountId,
+		kitty_id: [u8; 32],
+		start_price: BalanceOf<T>,
+		end_price: BalanceOf<T>,
+		start_block: BlockNumberFor<T>,
+		end_block: BlockNumberFor<T>,
+	) -> DispatchResult {
+		let mut kitty = Kitties::<T>::get(kitty_id).ok_or(Error::<T>::NoKitty)?;
+		ensure!(kitty.owner == caller, Error::<T>::NotOwner);
+		Self::ensure_unlocked(kitty_id)?;
+		let now = frame_system::Pallet::<T>::block_number();
+		ensure!(
+			start_price >= end_price && start_block < end_block && end_block > now,
+			Error::<T>::InvalidAuction
+		);
+
+		ListingExpiries::<T>::remove(kitty_id);
+		ListingAssets::<T>::remove(kitty_id);
+		kitty.price = None;
+		Kitties::<T>::insert(kitty_id, kitty);
+		let auction = DutchAuction { start_price, end_price, start_block, end_block };
+		DutchAuctions::<T>::insert(kitty_id, auction);
+
+		Self::deposit_event(Event::<T>::DutchAuctionCreated {
+			owner: caller,
+			kitty_id,
+			start_price,
+			end_price,
+			start_block,
+			end_block,
+		});
+		Ok(())
+	}
+
+	/// The price of a Dutch auction at block `now`, or `None` if the sale has not started yet.
+	pub fn dutch_auction_price(
+		auction: &DutchAuction<T>,
+		now: BlockNumberFor<T>,
+	) -> Option<BalanceOf<T>> {
+		if now < auction.start_block {
+			return None
+		}
+		let elapsed: u128 =
+			now.min(auction.end_block).saturating_sub(auction.start_block).saturated_into();
+		let duration: u128 = auction.end_block.saturating_sub(auction.start_block).saturated_into();
+		let start_price: u128 = auction.start_price.saturated_into();
+		let end_price: u128 = auction.end_price.saturated_into();
+		let discount =
+			start_price.saturating_sub(end_price).saturating_mul(elapsed) / duration.max(1);
+		Some(start_price.saturating_sub(discount).saturated_into())
+	}
+
+	/// Locks the kitty until it is claimed by the holder of the key hashing to
+	/// `claim_pubkey_hash`, or taken back once the gift expires.
+	pub fn do_create_gift(
+		gifter: T::AccountId,
+		kitty_id: [u8; 32],
+		claim_pubkey_hash: [u8; 32],
+	) -> DispatchResult {
+		let kitty = Kitties::<T>::get(kitty_id).ok_or(Error::<T>::NoKitty)?;
+		ensure!(kitty.owner == gifter, Error::<T>::NotOwner);
+		Self::ensure_unlocked(kitty_id)?;
+
+		let expires =
+			frame_system::Pallet::<T>::block_number().saturating_add(T::GiftExpiry::get());
+		let gift = Gift { gifter: gifter.clone(), claim_pubkey_hash, expires };
+		Gifts::<T>::insert(kitty_id, gift);
+
+		Self::deposit_event(Event::<T>::GiftCreated { gifter, kitty_id, expires });
+		Ok(())
+	}
+
+	/// Returns the gift if `signature` is a signature of `recipient` by the claim key of a gift
+	/// that has not expired.
+	pub fn check_gift_claim(
+		kitty_id: &[u8; 32],
+		claim_pubkey: &T::GiftPublic,
+		signature: &T::GiftSignature,
+		recipient: &T::AccountId,
+	) -> Result<Gift<T>, DispatchError> {
+		let gift = Gifts::<T>::get(kitty_id).ok_or(Error::<T>::NoGift)?;
+		let now = frame_system::Pallet::<T>::block_number();
+		ensure!(now < gift.expires, Error::<T>::GiftExpired);
+		let claim_pubkey_hash: [u8; 32] = BlakeTwo256::hash_of(claim_pubkey).into();
+		ensure!(claim_pubkey_hash == gift.claim_pubkey_hash, Error::<T>::InvalidGiftClaim);
+		let payload = (b"claim_gift", kitty_id, recipient).encode();
+		ensure!(
+			signature.verify(&payload[..], &claim_pubkey.clone().into_account()),
+			Error::<T>::InvalidGiftClaim
+		);
+		Ok(gift)
+	}
+
+	pub fn do_claim_gift(
+		kitty_id: [u8; 32],
+		claim_pubkey: T::GiftPublic,
+		signature: T::GiftSignature,
+		recipient: T::AccountId,
+	) -> DispatchResult {
+		let gift = Self::check_gift_claim(&kitty_id, &claim_pubkey, &signature, &recipient)?;
+		Gifts::<T>::remove(kitty_id);
+		Self::do_transfer(gift.gifter.clone(), recipient.clone(), kitty_id)?;
+
+		Self::deposit_event(Event::<T>::GiftClaimed { gifter: gift.gifter, recipient, kitty_id });
+		Ok(())
+	}
+
+	/// Unlocks an expired, unclaimed gift for the gifter.
+	pub fn do_reclaim_gift(gifter: T::AccountId, kitty_id: [u8; 32]) -> DispatchResult {
+		let gift = Gifts::<T>::get(kitty_id).ok_or(Error::<T>::NoGift)?;
+		ensure!(gift.gifter == gifter, Error::<T>::NotOwner);
+		let now = frame_system::Pallet::<T>::block_number();
+		ensure!(now >= gift.expires, Error::<T>::GiftNotExpired);
+		Gifts::<T>::remove(kitty_id);
+
+		Self::deposit_event(Event::<T>::GiftReclaimed { gifter, kitty_id });
+		Ok(())
+	}
+
+	/// Whether the calls of this pallet are currently halted.
+	pub fn is_paused() -> bool {
+		Paused::<T>::get()
+	}
+
+	/// Returns an error if the kitty is currently locked and cannot change hands.
+	pub fn ensure_unlocked(kitty_id: [u8; 32]) -> DispatchResult {
+		ensure!(!Lendings::<T>::contains_key(kitty_id), Error::<T>::KittyLent);
+		Self::ensure_unlocked_except_lending(kitty_id)
+	}
+
+	/// Like [`Self::ensure_unlocked`], but lets a lent kitty through.
+	pub fn ensure_unlocked_except_lending(kitty_id: [u8; 32]) -> DispatchResult {
+		ensure!(!Fractions::<T>::contains_key(kitty_id), Error::<T>::KittyFractionalized);
+		ensure!(!Stakes::<T>::contains_key(kitty_id), Error::<T>::KittyStaked);
+		ensure!(!TournamentEntries::<T>::contains_key(kitty_id), Error::<T>::KittyInTournament);
+		ensure!(!Gifts::<T>::contains_key(kitty_id), Error::<T>::KittyGifted);
+		ensure!(!Swaps::<T>::contains_key(kitty_id), Error::<T>::KittyInSwap);
+		Ok(())
+	}
+
+	/// The account currently allowed to use the kitty: the borrower while it is lent, otherwise
+	/// the owner.
+	pub fn user_of(kitty_id: [u8; 32]) -> Option<T::AccountId> {
+		if let Some(Lending { borrower, expires: Some(_), .. }) = Lendings::<T>::get(kitty_id) {
+			return Some(borrower)
+		}
+		Kitties::<T>::get(kitty_id).map(|kitty| kitty.owner)
+	}
+
+	pub fn do_lend(
+		lender: T::AccountId,
+		kitty_id: [u8; 32],
+		borrower: T::AccountId,
+		duration: BlockNumberFor<T>,
+		fee: BalanceOf<T>,
+	) -> DispatchResult {
+		ensure!(lender != borrower, Error::<T>::TransferToSelf);
+		ensure!(!duration.is_zero(), Error::<T>::InvalidDuration);
+		let kitty = Kitties::<T>::get(kitty_id).ok_or(Error::<T>::NoKitty)?;
+		ensure!(kitty.owner == lender, Error::<T>::NotOwner);
+		Self::ensure_unlocked(kitty_id)?;
+
+		let lending = Lending {
+			lender: lender.clone(),
+			borrower: borrower.clone(),
+			duration,
+			fee,
+			expires: None,
+		};
+		Lendings::<T>::insert(kitty_id, lending);
+
+		Self::deposit_event(Event::<T>::LendOffered { lender, borrower, kitty_id, duration, fee });
+		Ok(())
+	}
+
+	pub fn do_cancel_lend(lender: T::AccountId, kitty_id: [u8; 32]) -> DispatchResult {
+		let lending = Lendings::<T>::get(kitty_id).ok_or(Error::<T>::NotLent)?;
+		ensure!(lending.lender == lender, Error::<T>::NotOwner);
+		ensure!(lending.expires.is_none(), Error::<T>::AlreadyBorrowed);
+		Lendings::<T>::remove(kitty_id);
+
+		Self::deposit_event(Event::<T>::LendCancelled { lender, kitty_id });
+		Ok(())
+	}
+
+	pub fn do_accept_lend(
+		borrower: T::AccountId,
+		kitty_id: [u8; 32],
+		max_fee: BalanceOf<T>,
+	) -> DispatchResult {
+		let mut lending = Lendings::<T>::get(kitty_id).ok_or(Error::<T>::NotLent)?;
+		ensure!(lending.borrower == borrower, Error::<T>::NotBorrower);
+		ensure!(lending.expires.is_none(), Error::<T>::AlreadyBorrowed);
+		ensure!(max_fee >= lending.fee, Error::<T>::MaxFeeTooLow);
+
+		let now = frame_system::Pallet::<T>::block_number();
+		let expires = now.saturating_add(lending.duration);
+		LendingExpiries::<T>::try_append(expires, kitty_id)
+			.map_err(|_| Error::<T>::TooManyLendingsExpiring)?;
+
+		T::NativeBalance::transfer(
+			&borrower,
+			&lending.lender,
+			lending.fee,
+			Preservation::Preserve,
+		)?;
+		lending.expires = Some(expires);
+		let lender = lending.lender.clone();
+		Lendings::<T>::insert(kitty_id, lending);
+
+		Self::deposit_event(Event::<T>::Lent { lender, borrower, kitty_id, expires });
+		Ok(())
+	}
+
+	/// Returns every kitty whose lending expires at block `now` to its owner.
+	pub fn expire_lendings(now: BlockNumberFor<T>) -> Weight {
+		let expiring = LendingExpiries::<T>::take(now);
+		let count = expiring.len() as u64;
+		for kitty_id in expiring {
+			if let Some(lending) = Lendings::<T>::take(kitty_id) {
+				Self::deposit_event(Event::<T>::LendingExpired {
+					borrower: lending.borrower,
+					kitty_id,
+				});
+			}
+		}
+		T::DbWeight::get().reads_writes(1 + count, 1 + count)
+	}
+
+	/// Locks the kitty in the pallet and mints `shares` to its owner. The kitty stays recorded
+	/// under its owner, but cannot change hands until all shares are reclaimed by one account.
+	pub fn do_fractionalize(
+		owner: T::AccountId,
+		kitty_id: [u8; 32],
+		shares: u128,
+	) -> DispatchResult {
+		ensure!(!shares.is_zero(), Error::<T>::InvalidShares);
+		let kitty = Kitties::<T>::get(kitty_id).ok_or(Error::<T>::NoKitty)?;
+		ensure!(kitty.owner == owner, Error::<T>::NotOwner);
+		Self::ensure_unlocked(kitty_id)?;
+
+		Fractions::<T>::insert(kitty_id, shares);
+		Shares::<T>::insert(kitty_id, &owner, shares);
+
+		Self::deposit_event(Event::<T>::Fractionalized { owner, kitty_id, shares });
+		Ok(())
+	}
+
+	pub fn do_transfer_shares(
+		from: T::AccountId,
+		kitty_id: [u8; 32],
+		to: T::AccountId,
+		amount: u128,
+	) -> DispatchResult {
+		ensure!(from != to, Error::<T>::TransferToSelf);
+		ensure!(!amount.is_zero(), Error::<T>::InvalidShares);
+		ensure!(Fractions::<T>::contains_key(kitty_id), Error::<T>::NotFractionalized);
+
+		let from_shares = Shares::<T>::get(kitty_id, &from);
+		let remaining = from_shares.checked_sub(amount).ok_or(Error::<T>::InsufficientShares)?;
+		if remaining.is_zero() {
+			Shares::<T>::remove(kitty_id, &from);
+		} else {
+			Shares::<T>::insert(kitty_id, &from, remaining);
+		}
+		// Cannot overflow, the sum of all holdings is the total supply of shares.
+		Shares::<T>::mutate(kitty_id, &to, |shares| *shares = shares.saturating_add(amount));
+
+		Self::deposit_event(Event::<T>::SharesTransferred { from, to, kitty_id, amount });
+		Ok(())
+	}
+
+	/// Unlocks the kitty for the account holding every share, transferring it if needed.
+	pub fn do_reclaim(who: T::AccountId, kitty_id: [u8; 32]) -> DispatchResult {
+		let total = Fractions::<T>::get(kitty_id).ok_or(Error::<T>::NotFractionalized)?;
+		ensure!(Shares::<T>::get(kitty_id, &who) == total, Error::<T>::InsufficientShares);
+		let kitty = Kitties::<T>::get(kitty_id).ok_or(Error::<T>::NoKitty)?;
 
-		Self::deposit_event(Event::<T>::Sold { buyer, kitty_id, price: real_price });
+		Fractions::<T>::remove(kitty_id);
+		Shares::<T>::remove(kitty_id, &who);
+		if kitty.owner != who {
+			Self::do_transfer(kitty.owner, who.clone(), kitty_id)?;
+		}
+
+		Self::deposit_event(Event::<T>::Reclaimed { owner: who, kitty_id });
+		Ok(())
+	}
+
+	/// Trains the kitty, which can only be done by its current user once per cooldown. A
+	/// fractionalized kitty belongs to its shareholders and cannot be trained.
+	pub fn do_train(who: T::AccountId, kitty_id: [u8; 32]) -> DispatchResult {
+		let user = Self::user_of(kitty_id).ok_or(Error::<T>::NoKitty)?;
+		ensure!(user == who, Error::<T>::NotOwner);
+		ensure!(!Fractions::<T>::contains_key(kitty_id), Error::<T>::KittyFractionalized);
+
+		let now = frame_system::Pallet::<T>::block_number();
+		if let Some(ready_at) = TrainingCooldowns::<T>::get(kitty_id) {
+			ensure!(now >= ready_at, Error::<T>::TrainingCooldown);
+		}
+		TrainingCooldowns::<T>::insert(kitty_id, now.saturating_add(T::TrainCooldown::get()));
+
+		Self::add_xp(kitty_id, T::XpPerTrain::get())
+	}
+
+	/// Grants experience to the kitty, levelling it up as needed.
+	pub fn add_xp(kitty_id: [u8; 32], amount: u32) -> DispatchResult {
+		let mut kitty = Kitties::<T>::get(kitty_id).ok_or(Error::<T>::NoKitty)?;
+		kitty.xp = kitty.xp.saturating_add(amount);
+
+		let xp_per_level = T::XpPerLevel::get().max(1);
+		let level = (kitty.xp / xp_per_level).saturating_add(1).min(T::MaxLevel::get());
+		let levelled_up = level > kitty.level;
+		if levelled_up {
+			kitty.level = level;
+		}
+		let (xp, level) = (kitty.xp, kitty.level);
+		Kitties::<T>::insert(kitty_id, kitty);
+
+		Self::deposit_event(Event::<T>::Trained { kitty_id, xp, level });
+		if levelled_up {
+			Self::deposit_event(Event::<T>::LevelUp { kitty_id, level });
+		}
+		Ok(())
+	}
+
+	/// The account of the pallet, holding escrowed funds.
+	pub fn account_id() -> T::AccountId {
+		T::PalletId::get().into_account_truncating()
+	}
+
+	/// Escrows the mint price. The kitty is minted at the start of the block after `target`,
+	/// from the hash of block `target`, which does not exist yet. Neither the minter nor anyone
+	/// else can act between the hash becoming known and the mint.
+	pub fn do_commit_mint(who: T::AccountId) -> DispatchResult {
+		ensure!(!MintCommits::<T>::contains_key(&who), Error::<T>::CommitPending);
+
+		let deposit = T::MintPrice::get();
+		T::NativeBalance::transfer(&who, &Self::account_id(), deposit, Preservation::Preserve)?;
+		let target = Self::queue_mint_commit(&who, deposit, 0)?;
+
+		Self::deposit_event(Event::<T>::MintCommitted { who, target });
+		Ok(())
+	}
+
+	/// Stores a mint commit of `who` and queues it for the block after its target, returned.
+	fn queue_mint_commit(
+		who: &T::AccountId,
+		deposit: BalanceOf<T>,
+		min_tier: u8,
+	) -> Result<BlockNumberFor<T>, DispatchError> {
+		let now = frame_system::Pallet::<T>::block_number();
+		let target = now.saturating_add(T::RevealDelay::get());
+		MintCommitQueue::<T>::try_append(target.saturating_add(One::one()), who)
+			.map_err(|_| Error::<T>::TooManyCommits)?;
+		MintCommits::<T>::insert(who, MintCommit { deposit, target, min_tier });
+		Ok(target)
+	}
+
+	/// The DNA of a committed mint, derived from the hash of its target block.
+	fn committed_dna(who: &T::AccountId, target_hash: T::Hash) -> [u8; 32] {
+		BlakeTwo256::hash_of(&(target_hash, who)).into()
+	}
+
+	/// Mints the kitty of every commit revealed at block `now`. The deposit is refunded instead
+	/// when the kitty cannot be minted, e.g. because the minter owns too many kitties, or when
+	/// the target block hash is not known.
+	pub fn reveal_mint_commits(now: BlockNumberFor<T>) -> Weight {
+		let revealed = MintCommitQueue::<T>::take(now);
+		let count = revealed.len() as u64;
+		for who in revealed {
+			let Some(commit) = MintCommits::<T>::get(&who) else { continue };
+			let target_hash = frame_system::Pallet::<T>::block_hash(commit.target);
+			if target_hash != T::Hash::default() {
+				let mut dna = Self::committed_dna(&who, target_hash);
+				if Self::rarity_tier(&dna) < commit.min_tier {
+					dna = Self::with_rarity_tier(dna, commit.min_tier);
+				}
+				if Self::mint(who.clone(), dna).is_ok() {
+					MintCommits::<T>::remove(&who);
+					Self::deposit_event(Event::<T>::MintRevealed { who, kitty_id: dna });
+					continue
+				}
+			}
+			// The commit is kept until the refund can be claimed with `claim_mint_refund`.
+			if Self::refund_mint_commit(who.clone(), commit).is_err() {
+				Self::deposit_event(Event::<T>::MintRefundFailed { who });
+			}
+		}
+		// Minting reads and writes the kitty, the owned kitties, the count and every trait count.
+		T::DbWeight::get().reads_writes(1 + 14 * count, 1 + 13 * count)
+	}
+
+	/// Refunds a mint commit whose refund failed when it was revealed.
+	pub fn do_claim_mint_refund(who: T::AccountId) -> DispatchResult {
+		let commit = MintCommits::<T>::get(&who).ok_or(Error::<T>::NoCommit)?;
+		let now = frame_system::Pallet::<T>::block_number();
+		ensure!(now > commit.target, Error::<T>::RevealTooEarly);
+		Self::refund_mint_commit(who, commit)
+	}
+
+	fn refund_mint_commit(who: T::AccountId, commit: MintCommit<T>) -> DispatchResult {
+		let refund = commit.deposit;
+		T::NativeBalance::transfer(&Self::account_id(), &who, refund, Preservation::Expendable)?;
+		MintCommits::<T>::remove(&who);
+
+		Self::deposit_event(Event::<T>::MintRefunded { who, refund });
+		Ok(())
+	}
+
+	/// Opens a swap of `offered_kitty` against a kitty matching `desired`, escrowing the swap
+	/// deposit and the optional top-up until the swap is claimed or cancelled. The offered kitty
+	/// is locked meanwhile.
+	pub fn do_create_swap(
+		creator: T::AccountId,
+		offered_kitty: [u8; 32],
+		desired: SwapTarget,
+		top_up: Option<BalanceOf<T>>,
+	) -> DispatchResult {
+		let kitty = Kitties::<T>::get(offered_kitty).ok_or(Error::<T>::NoKitty)?;
+		ensure!(kitty.owner == creator, Error::<T>::NotOwner);
+		ensure!(!Swaps::<T>::contains_key(offered_kitty), Error::<T>::SwapExists);
+		Self::ensure_unlocked(offered_kitty)?;
+
+		if let SwapTarget::Kitty(desired_kitty) = desired {
+			let other = Kitties::<T>::get(desired_kitty).ok_or(Error::<T>::NoKitty)?;
+			ensure!(other.owner != creator, Error::<T>::InvalidSwap);
+			SwapsForKitty::<T>::try_append(desired_kitty, offered_kitty)
+				.map_err(|_| Error::<T>::TooManySwaps)?;
+		}
+		let deposit = T::SwapDeposit::get();
+		if !deposit.is_zero() {
+			T::NativeBalance::transfer(
+				&creator,
+				&Self::account_id(),
+				deposit,
+				Preservation::Preserve,
+			)?;
+		}
+		if let Some(amount) = top_up {
+			T::NativeBalance::transfer(
+				&creator,
+				&Self::account_id(),
+				amount,
+				Preservation::Preserve,
+			)?;
+		}
+		let swap = Swap { creator: creator.clone(), desired: desired.clone(), top_up, deposit };
+		Swaps::<T>::insert(offered_kitty, swap);
+
+		Self::deposit_event(Event::<T>::SwapCreated { creator, offered_kitty, desired, top_up });
+		Ok(())
+	}
+
+	/// Exchanges the offered kitty against `kitty_id` and pays out the escrowed top-up.
+	pub fn do_claim_swap(
+		claimer: T::AccountId,
+		offered_kitty: [u8; 32],
+		kitty_id: [u8; 32],
+	) -> DispatchResult {
+		let swap = Swaps::<T>::get(offered_kitty).ok_or(Error::<T>::NoSwap)?;
+		let kitty = Kitties::<T>::get(kitty_id).ok_or(Error::<T>::NoKitty)?;
+		ensure!(kitty.owner == claimer, Error::<T>::NotOwner);
+		ensure!(swap.creator != claimer, Error::<T>::TransferToSelf);
+		ensure!(swap.desired.matches(&kitty_id, &kitty.dna), Error::<T>::SwapMismatch);
+
+		Self::remove_swap(offered_kitty, &swap)?;
+		if let Some(amount) = swap.top_up {
+			T::NativeBalance::transfer(
+				&Self::account_id(),
+				&claimer,
+				amount,
+				Preservation::Expendable,
+			)?;
+		}
+		Self::do_transfer(swap.creator.clone(), claimer.clone(), offered_kitty)?;
+		Self::do_transfer(claimer.clone(), swap.creator.clone(), kitty_id)?;
+
+		Self::deposit_event(Event::<T>::SwapClaimed {
+			creator: swap.creator,
+			claimer,
+			offered_kitty,
+			received_kitty: kitty_id,
+		});
+		Ok(())
+	}
+
+	pub fn do_cancel_swap(creator: T::AccountId, offered_kitty: [u8; 32]) -> DispatchResult {
+		let swap = Swaps::<T>::get(offered_kitty).ok_or(Error::<T>::NoSwap)?;
+		ensure!(swap.creator == creator, Error::<T>::NotOwner);
+		Self::cancel_swap_of(offered_kitty)
+	}
+
+	/// Cancels the swap offering `offered_kitty` and refunds its top-up to the creator.
+	pub fn cancel_swap_of(offered_kitty: [u8; 32]) -> DispatchResult {
+		let swap = Swaps::<T>::get(offered_kitty).ok_or(Error::<T>::NoSwap)?;
+		Self::remove_swap(offered_kitty, &swap)?;
+		if let Some(amount) = swap.top_up {
+			T::NativeBalance::transfer(
+				&Self::account_id(),
+				&swap.creator,
+				amount,
+				Preservation::Expendable,
+			)?;
+		}
+
+		Self::deposit_event(Event::<T>::SwapCancelled { creator: swap.creator, offered_kitty });
 		Ok(())
 	}
+
+	/// Cancels every swap offering or asking for `kitty_id`, as it is about to change hands.
+	fn cancel_swaps_involving(kitty_id: [u8; 32]) -> DispatchResult {
+		if Swaps::<T>::contains_key(kitty_id) {
+			Self::cancel_swap_of(kitty_id)?;
+		}
+		for offered_kitty in SwapsForKitty::<T>::take(kitty_id) {
+			Self::cancel_swap_of(offered_kitty)?;
+		}
+		Ok(())
+	}
+
+	/// Removes the swap and refunds its deposit to the creator.
+	fn remove_swap(offered_kitty: [u8; 32], swap: &Swap<T>) -> DispatchResult {
+		Swaps::<T>::remove(offered_kitty);
+		if !swap.deposit.is_zero() {
+			T::NativeBalance::transfer(
+				&Self::account_id(),
+				&swap.creator,
+				swap.deposit,
+				Preservation::Expendable,
+			)?;
+		}
+		if let SwapTarget::Kitty(desired_kitty) = swap.desired {
+			SwapsForKitty::<T>::mutate_exists(desired_kitty, |maybe_offered| {
+				if let Some(offered) = maybe_offered {
+					offered.retain(|id| *id != offered_kitty);
+					if offered.is_empty() {
+						*maybe_offered = None;
+					}
+				}
+			});
+		}
+		Ok(())
+	}
+
+	/// The rarity tier of a DNA, from 0 (common) to 8. Each tier is half as likely as the one
+	/// below it.
+	pub fn rarity_tier(dna: &[u8; 32]) -> u8 {
+		dna[0].leading_zeros() as u8
+	}
+
+	/// Rewrites the DNA so that it belongs to the given rarity tier, capped at 8.
+	pub fn with_rarity_tier(mut dna: [u8; 32], tier: u8) -> [u8; 32] {
+		dna[0] = if tier >= 8 { 0 } else { (dna[0] | 0x80) >> tier };
+		dna
+	}
+
+	/// The account rewards are paid from. Anyone can fund it.
+	pub fn reward_pot() -> T::AccountId {
+		T::PalletId::get().into_sub_account_truncating(b"rewards")
+	}
+
+	/// Accrues the rewards of every block since the last update into `RewardPerWeight`.
+	fn update_rewards() -> u128 {
+		let now = frame_system::Pallet::<T>::block_number();
+		let last = LastRewardBlock::<T>::get();
+		let mut reward_per_weight = RewardPerWeight::<T>::get();
+		if now <= last {
+			return reward_per_weight
+		}
+
+		let total_weight = TotalStakeWeight::<T>::get();
+		if !total_weight.is_zero() {
+			let blocks: u128 = now.saturating_sub(last).saturated_into();
+			let reward = T::RewardPerBlock::get().saturated_into::<u128>().saturating_mul(blocks);
+			reward_per_weight = reward_per_weight
+				.saturating_add(reward.saturating_mul(REWARD_PRECISION) / total_weight);
+			RewardPerWeight::<T>::put(reward_per_weight);
+		}
+		LastRewardBlock::<T>::put(now);
+		reward_per_weight
+	}
+
+	/// Pays the rewards accrued by `stake` up to `reward_per_weight`, limited by the reward pot.
+	/// Whatever the pot cannot cover stays owed, and is returned.
+	fn pay_rewards(
+		kitty_id: [u8; 32],
+		stake: &mut Stake<T>,
+		reward_per_weight: u128,
+	) -> Result<u128, DispatchError> {
+		let accrued = stake.weight.saturating_mul(reward_per_weight) / REWARD_PRECISION;
+		let pending = accrued.saturating_sub(stake.reward_debt);
+
+		let pot = Self::reward_pot();
+		let available =
+			T::NativeBalance::reducible_balance(&pot, Preservation::Expendable, Fortitude::Polite);
+		let amount = BalanceOf::<T>::saturated_from(pending).min(available);
+		let paid: u128 = amount.saturated_into();
+		stake.reward_debt = stake.reward_debt.saturating_add(paid);
+		if !amount.is_zero() {
+			T::NativeBalance::transfer(&pot, &stake.owner, amount, Preservation::Expendable)?;
+			Self::deposit_event(Event::<T>::RewardsClaimed {
+				owner: stake.owner.clone(),
+				kitty_id,
+				amount,
+			});
+		}
+		Ok(pending.saturating_sub(paid))
+	}
+
+	pub fn do_fund_rewards(who: T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+		T::NativeBalance::transfer(&who, &Self::reward_pot(), amount, Preservation::Preserve)?;
+		Ok(())
+	}
+
+	/// Locks the kitty and starts accruing rewards for its owner.
+	pub fn do_stake(owner: T::AccountId, kitty_id: [u8; 32]) -> DispatchResult {
+		let kitty = Kitties::<T>::get(kitty_id).ok_or(Error::<T>::NoKitty)?;
+		ensure!(kitty.owner == owner, Error::<T>::NotOwner);
+		Self::ensure_unlocked(kitty_id)?;
+
+		let reward_per_weight = Self::update_rewards();
+		let weight = 1u128 << Self::rarity_tier(&kitty.dna);
+		let reward_debt = weight.saturating_mul(reward_per_weight) / REWARD_PRECISION;
+		TotalStakeWeight::<T>::mutate(|total| *total = total.saturating_add(weight));
+		Stakes::<T>::insert(kitty_id, Stake { owner: owner.clone(), weight, reward_debt });
+
+		Self::deposit_event(Event::<T>::Staked { owner, kitty_id, weight });
+		Ok(())
+	}
+
+	pub fn do_claim_rewards(owner: T::AccountId, kitty_id: [u8; 32]) -> DispatchResult {
+		let mut stake = Stakes::<T>::get(kitty_id).ok_or(Error::<T>::NotStaked)?;
+		ensure!(stake.owner == owner, Error::<T>::NotOwner);
+
+		let reward_per_weight = Self::update_rewards();
+		Self::pay_rewards(kitty_id, &mut stake, reward_per_weight)?;
+		Stakes::<T>::insert(kitty_id, stake);
+		Ok(())
+	}
+
+	/// Pays out the pending rewards and unlocks the kitty. Rewards the pot cannot cover are
+	/// forfeited.
+	pub fn do_unstake(owner: T::AccountId, kitty_id: [u8; 32]) -> DispatchResult {
+		let mut stake = Stakes::<T>::get(kitty_id).ok_or(Error::<T>::NotStaked)?;
+		ensure!(stake.owner == owner, Error::<T>::NotOwner);
+
+		let reward_per_weight = Self::update_rewards();
+		let unpaid = Self::pay_rewards(kitty_id, &mut stake, reward_per_weight)?;
+		if !unpaid.is_zero() {
+			Self::deposit_event(Event::<T>::RewardsForfeited {
+				owner: owner.clone(),
+				kitty_id,
+				amount: BalanceOf::<T>::saturated_from(unpaid),
+			});
+		}
+		TotalStakeWeight::<T>::mutate(|total| *total = total.saturating_sub(stake.weight));
+		Stakes::<T>::remove(kitty_id);
+
+		Self::deposit_event(Event::<T>::Unstaked { owner, kitty_id });
+		Ok(())
+	}
+
+	/// Clears the price of every listing that expired up to block `now`, processing as many
+	/// blocks of `ListingExpiryQueue` as `limit` allows.
+	pub fn expire_listings(now: BlockNumberFor<T>, limit: Weight) -> Weight {
+		let db_weight = T::DbWeight::get();
+		let per_listing = db_weight.reads_writes(2, 3);
+		let per_block = db_weight.reads_writes(1, 1).saturating_add(
+			per_listing.saturating_mul(T::MaxListingExpiriesPerBlock::get().into()),
+		);
+		let mut used = db_weight.reads_writes(1, 1);
+		if !limit.all_gte(used.saturating_add(per_block)) {
+			return Weight::zero()
+		}
+
+		let mut block = NextListingExpiry::<T>::get();
+		if block.is_zero() {
+			block = now;
+		}
+		while block <= now && limit.all_gte(used.saturating_add(per_block)) {
+			for kitty_id in ListingExpiryQueue::<T>::take(block) {
+				// The listing was changed or cleared since it was queued.
+				if ListingExpiries::<T>::get(kitty_id) != Some(block) {
+					continue
+				}
+				ListingExpiries::<T>::remove(kitty_id);
+				ListingAssets::<T>::remove(kitty_id);
+				if let Some(mut kitty) = Kitties::<T>::get(kitty_id) {
+					kitty.price = None;
+					let owner = kitty.owner.clone();
+					Kitties::<T>::insert(kitty_id, kitty);
+					Self::deposit_event(Event::<T>::ListingExpired { owner, kitty_id });
+				}
+			}
+			used = used.saturating_add(per_block);
+			block = block.saturating_add(One::one());
+		}
+		NextListingExpiry::<T>::put(block);
+		used
+	}
+
+	/// Appends `previous_owner` to the ownership history of the kitty, dropping the oldest entry
+	/// once the history is full.
+	fn record_previous_owner(kitty_id: [u8; 32], previous_owner: T::AccountId) {
+		let now = frame_system::Pallet::<T>::block_number();
+		OwnershipHistory::<T>::mutate(kitty_id, |history| {
+			if !history.is_empty() && history.len() >= T::MaxOwnershipHistory::get() as usize {
+				history.remove(0);
+			}
+			let _ = history.try_push((previous_owner, now, None));
+		});
+	}
+
+	/// Records the price of the sale that produced the latest ownership history entry.
+	fn record_sale_price(kitty_id: [u8; 32], price: BalanceOf<T>) {
+		OwnershipHistory::<T>::mutate(kitty_id, |history| {
+			if let Some((_, _, sale_price)) = history.last_mut() {
+				*sale_price = Some(price);
+			}
+		});
+	}
+
+	/// The kitty with the given id, if it exists.
+	pub fn kitty(kitty_id: [u8; 32]) -> Option<Kitty<T>> {
+		Kitties::<T>::get(kitty_id)
+	}
+
+	/// The ownership history of a kitty, oldest first.
+	pub fn ownership_history(kitty_id: [u8; 32]) -> Vec<OwnershipRecord<T>> {
+		OwnershipHistory::<T>::get(kitty_id).into_inner()
+	}
 }
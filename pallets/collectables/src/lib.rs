@@ -1,17 +1,36 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+mod battle;
+mod free_mint;
 mod impls;
+pub mod migrations;
+mod rarity;
+#[cfg(test)]
+mod tests;
 
+use frame::deps::frame_support::PalletId;
+use frame::deps::sp_runtime::traits::IdentifyAccount;
+use frame::deps::sp_runtime::traits::Verify;
+use frame::deps::sp_runtime::Permill;
+use frame::deps::sp_runtime::SaturatedConversion;
 use frame::prelude::*;
 use frame::traits::fungible::Inspect;
 use frame::traits::fungible::Mutate;
+use frame::traits::fungibles;
+use frame::traits::Saturating;
+pub use free_mint::{FreeFirstMint, FreeMintPre};
 pub use pallet::*;
 
 #[frame::pallet(dev_mode)]
 pub mod pallet {
 	use super::*;
 
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+
 	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(core::marker::PhantomData<T>);
 
 	#[pallet::config]
@@ -20,12 +39,133 @@ pub mod pallet {
 
 		
 		type NativeBalance: Inspect<Self::AccountId> + Mutate<Self::AccountId>;
+
+		/// The assets kitties can be priced in besides the native token.
+		type Assets: fungibles::Inspect<Self::AccountId, Balance = BalanceOf<Self>>
+			+ fungibles::Mutate<Self::AccountId>;
+
+		/// The maximum number of lendings that can expire in the same block.
+		#[pallet::constant]
+		type MaxLendingsPerBlock: Get<u32>;
+
+		/// The number of blocks a kitty must rest between two trainings.
+		#[pallet::constant]
+		type TrainCooldown: Get<BlockNumberFor<Self>>;
+
+		/// The experience a kitty gains from a single training.
+		#[pallet::constant]
+		type XpPerTrain: Get<u32>;
+
+		/// The experience needed to advance one level.
+		#[pallet::constant]
+		type XpPerLevel: Get<u32>;
+
+		/// The highest level a kitty can reach. Each fused kitty at this level raises the rarity
+		/// tier of the fusion by one.
+		#[pallet::constant]
+		type MaxLevel: Get<u32>;
+
+		/// The pallet id, used to derive the account holding escrowed funds.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// The price paid when committing to a mint.
+		#[pallet::constant]
+		type MintPrice: Get<BalanceOf<Self>>;
+
+		/// The number of blocks after a commit whose block hash seeds the kitty DNA. The kitty is
+		/// minted at the start of the following block.
+		#[pallet::constant]
+		type RevealDelay: Get<BlockNumberFor<Self>>;
+
+		/// The maximum number of mint commits that can be revealed in the same block.
+		#[pallet::constant]
+		type MaxCommitsPerBlock: Get<u32>;
+
+		/// The reward paid out of the reward pot every block, shared by all staked kitties in
+		/// proportion to their rarity.
+		#[pallet::constant]
+		type RewardPerBlock: Get<BalanceOf<Self>>;
+
+		/// The maximum number of listings that can expire in the same block.
+		#[pallet::constant]
+		type MaxListingExpiriesPerBlock: Get<u32>;
+
+		/// The number of previous owners remembered for each kitty.
+		#[pallet::constant]
+		type MaxOwnershipHistory: Get<u32>;
+
+		/// The largest number of kitties a tournament bracket can hold.
+		#[pallet::constant]
+		type MaxTournamentSize: Get<u32>;
+
+		/// The experience a kitty gains for every match it wins.
+		#[pallet::constant]
+		type XpPerWin: Get<u32>;
+
+		/// The origin allowed to pause and resume the marketplace.
+		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// The key a gift is claimed with, handed to the recipient in the claim link.
+		type GiftPublic: IdentifyAccount + Parameter;
+
+		/// The signature of the recipient account by a gift claim key.
+		type GiftSignature: Verify<Signer = Self::GiftPublic> + Parameter;
+
+		/// The number of blocks a gift can be claimed for before the gifter can take it back.
+		#[pallet::constant]
+		type GiftExpiry: Get<BlockNumberFor<Self>>;
+
+		/// The largest number of kitties that can be fused together.
+		#[pallet::constant]
+		type MaxFusionInputs: Get<u32>;
+
+		/// The share of every sale price paid to `FeeDestination` instead of the seller.
+		#[pallet::constant]
+		type MarketplaceFee: Get<Permill>;
+
+		/// The account collecting the marketplace fees, such as the treasury. It must be able to
+		/// receive fees in any asset kitties are priced in.
+		type FeeDestination: Get<Self::AccountId>;
+
+		/// The largest number of feeless first mints in a block, see [`FreeFirstMint`].
+		#[pallet::constant]
+		type MaxFreeMintsPerBlock: Get<u32>;
+
+		/// The number of blocks a feeless first mint stays valid in the transaction pool.
+		#[pallet::constant]
+		type FreeMintLongevity: Get<u64>;
+
+		/// The deposit held for every open swap, returned when it is claimed or cancelled.
+		#[pallet::constant]
+		type SwapDeposit: Get<BalanceOf<Self>>;
+
+		/// The maximum number of open swaps asking for the same kitty.
+		#[pallet::constant]
+		type MaxSwapsPerKitty: Get<u32>;
+
+		/// The number of blocks a tournament stays open for registration. After that, anyone can
+		/// cancel it if its bracket is not full.
+		#[pallet::constant]
+		type RegistrationPeriod: Get<BlockNumberFor<Self>>;
+
+		/// The number of blocks after a bracket fills up whose block hash seeds its matches.
+		#[pallet::constant]
+		type TournamentSeedDelay: Get<BlockNumberFor<Self>>;
 	}
 
 	
 	pub type BalanceOf<T> =
 		<<T as Config>::NativeBalance as Inspect<<T as frame_system::Config>::AccountId>>::Balance;
 
+	pub type AssetIdOf<T> = <<T as Config>::Assets as fungibles::Inspect<
+		<T as frame_system::Config>::AccountId,
+	>>::AssetId;
+
+	/// A previous owner of a kitty: `(previous_owner, block_number, sale_price)`.
+	pub type OwnershipRecord<T> =
+		(<T as frame_system::Config>::AccountId, BlockNumberFor<T>, Option<BalanceOf<T>>);
+
 	#[derive(Encode, Decode, TypeInfo, MaxEncodedLen)]
 	#[scale_info(skip_type_params(T))]
 	pub struct Kitty<T: Config> {
@@ -33,11 +173,136 @@ pub mod pallet {
 		pub dna: [u8; 32],
 		pub owner: T::AccountId,
 		pub price: Option<BalanceOf<T>>,
+		pub level: u32,
+		pub xp: u32,
+	}
+
+	/// A lending of a kitty from its owner to a temporary user.
+	///
+	/// The lending is only an offer until the borrower accepts it, at which point `expires` is set
+	/// and the borrower becomes the user of the kitty.
+	#[derive(Encode, Decode, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Lending<T: Config> {
+		pub lender: T::AccountId,
+		pub borrower: T::AccountId,
+		pub duration: BlockNumberFor<T>,
+		pub fee: BalanceOf<T>,
+		pub expires: Option<BlockNumberFor<T>>,
+	}
+
+	/// A commitment to mint a kitty whose DNA is only known once `target` is produced, paid by
+	/// a deposit or, for fusions, by the burned kitties.
+	#[derive(Encode, Decode, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct MintCommit<T: Config> {
+		pub deposit: BalanceOf<T>,
+		/// The block whose hash seeds the DNA, unknown at commit time.
+		pub target: BlockNumberFor<T>,
+		/// The lowest rarity tier of the minted kitty.
+		pub min_tier: u8,
+	}
+
+	#[derive(Encode, Decode, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Gift<T: Config> {
+		pub gifter: T::AccountId,
+		/// The hash of the public key whose holder can claim the kitty.
+		pub claim_pubkey_hash: [u8; 32],
+		pub expires: BlockNumberFor<T>,
+	}
+
+	/// The kitty a swap asks for in exchange of the offered one.
+	#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, PartialEq, Eq, RuntimeDebug)]
+	pub enum SwapTarget {
+		/// One specific kitty.
+		Kitty([u8; 32]),
+		/// Any kitty whose DNA equals `value` on every bit set in `mask`.
+		Traits { mask: [u8; 32], value: [u8; 32] },
+	}
+
+	impl SwapTarget {
+		pub fn matches(&self, kitty_id: &[u8; 32], dna: &[u8; 32]) -> bool {
+			match self {
+				SwapTarget::Kitty(id) => id == kitty_id,
+				SwapTarget::Traits { mask, value } =>
+					dna.iter().zip(mask).zip(value).all(|((d, m), v)| d & m == v & m),
+			}
+		}
+	}
+
+	#[derive(Encode, Decode, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Swap<T: Config> {
+		pub creator: T::AccountId,
+		pub desired: SwapTarget,
+		/// A balance escrowed by the creator and paid to whoever claims the swap.
+		pub top_up: Option<BalanceOf<T>>,
+		/// The deposit held from the creator until the swap is claimed or cancelled.
+		pub deposit: BalanceOf<T>,
+	}
+
+	#[derive(Encode, Decode, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Stake<T: Config> {
+		pub owner: T::AccountId,
+		/// The share of rewards this kitty earns, derived from its rarity tier.
+		pub weight: u128,
+		/// The rewards per weight already accounted for this stake.
+		pub reward_debt: u128,
+	}
+
+	/// A single-elimination tournament, resolved once its bracket is full.
+	#[derive(Encode, Decode, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Tournament<T: Config> {
+		pub organizer: T::AccountId,
+		pub entry_fee: BalanceOf<T>,
+		/// The number of entrants of the bracket, a power of two.
+		pub size: u32,
+		/// The registered kitties, with the account that entered them.
+		pub entrants: BoundedVec<([u8; 32], T::AccountId), T::MaxTournamentSize>,
+		pub prize_pool: BalanceOf<T>,
+		/// The block whose hash seeds the matches, set once the bracket is full.
+		pub seed_block: Option<BlockNumberFor<T>>,
+	}
+
+	/// A descending-price sale: the price falls linearly from `start_price` at `start_block` to
+	/// `end_price` at `end_block`, and stays there until the kitty is bought.
+	#[derive(Encode, Decode, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct DutchAuction<T: Config> {
+		pub start_price: BalanceOf<T>,
+		pub end_price: BalanceOf<T>,
+		pub start_block: BlockNumberFor<T>,
+		pub end_block: BlockNumberFor<T>,
 	}
 
 	#[pallet::storage]
 	pub(super) type CountForKitties<T: Config> = StorageValue<Value = u32, QueryKind = ValueQuery>;
 
+	/// The number of kitties having each value of each trait slot of the DNA.
+	#[pallet::storage]
+	pub(super) type TraitCounts<T: Config> =
+		StorageDoubleMap<Key1 = u8, Key2 = u8, Value = u32, QueryKind = ValueQuery>;
+
+	/// Whether the calls of this pallet are halted. Enforced by the call filter of the runtime.
+	#[pallet::storage]
+	pub(super) type Paused<T: Config> = StorageValue<Value = bool, QueryKind = ValueQuery>;
+
+	/// The kitties waiting to be claimed by the holder of a claim key.
+	#[pallet::storage]
+	pub(super) type Gifts<T: Config> = StorageMap<Key = [u8; 32], Value = Gift<T>>;
+
+	/// The accounts that already used their feeless first mint.
+	#[pallet::storage]
+	pub(super) type FreeMintUsed<T: Config> =
+		StorageMap<Key = T::AccountId, Value = bool, QueryKind = ValueQuery>;
+
+	/// The feeless mints included in the current block.
+	#[pallet::storage]
+	pub(super) type FreeMintsInBlock<T: Config> = StorageValue<Value = u32, QueryKind = ValueQuery>;
+
 	#[pallet::storage]
 	pub(super) type Kitties<T: Config> = StorageMap<Key = [u8; 32], Value = Kitty<T>>;
 
@@ -49,13 +314,218 @@ pub mod pallet {
 		QueryKind = ValueQuery,
 	>;
 
+	#[pallet::storage]
+	pub(super) type Tournaments<T: Config> = StorageMap<Key = u32, Value = Tournament<T>>;
+
+	#[pallet::storage]
+	pub(super) type NextTournamentId<T: Config> = StorageValue<Value = u32, QueryKind = ValueQuery>;
+
+	/// The tournament a kitty is registered in.
+	#[pallet::storage]
+	pub(super) type TournamentEntries<T: Config> = StorageMap<Key = [u8; 32], Value = u32>;
+
+	/// The block at which registration for a tournament closes.
+	#[pallet::storage]
+	pub(super) type TournamentDeadlines<T: Config> =
+		StorageMap<Key = u32, Value = BlockNumberFor<T>>;
+
+	/// The most recent previous owners of a kitty, oldest first, as
+	/// `(previous_owner, block_number, sale_price)`.
+	#[pallet::storage]
+	pub(super) type OwnershipHistory<T: Config> = StorageMap<
+		Key = [u8; 32],
+		Value = BoundedVec<OwnershipRecord<T>, T::MaxOwnershipHistory>,
+		QueryKind = ValueQuery,
+	>;
+
+	/// The asset a listed kitty is priced in, when not the native token.
+	#[pallet::storage]
+	pub(super) type ListingAssets<T: Config> = StorageMap<Key = [u8; 32], Value = AssetIdOf<T>>;
+
+	/// The block at which the price of a listed kitty stops being valid.
+	#[pallet::storage]
+	pub(super) type ListingExpiries<T: Config> =
+		StorageMap<Key = [u8; 32], Value = BlockNumberFor<T>>;
+
+	/// The listings expiring at a given block, cleared in `on_idle`.
+	#[pallet::storage]
+	pub(super) type ListingExpiryQueue<T: Config> = StorageMap<
+		Key = BlockNumberFor<T>,
+		Value = BoundedVec<[u8; 32], T::MaxListingExpiriesPerBlock>,
+		QueryKind = ValueQuery,
+	>;
+
+	#[pallet::storage]
+	pub(super) type DutchAuctions<T: Config> = StorageMap<Key = [u8; 32], Value = DutchAuction<T>>;
+
+	/// The next block of `ListingExpiryQueue` to be cleared.
+	#[pallet::storage]
+	pub(super) type NextListingExpiry<T: Config> =
+		StorageValue<Value = BlockNumberFor<T>, QueryKind = ValueQuery>;
+
+	#[pallet::storage]
+	pub(super) type Lendings<T: Config> = StorageMap<Key = [u8; 32], Value = Lending<T>>;
+
+	/// The kitties whose lending expires at a given block.
+	#[pallet::storage]
+	pub(super) type LendingExpiries<T: Config> = StorageMap<
+		Key = BlockNumberFor<T>,
+		Value = BoundedVec<[u8; 32], T::MaxLendingsPerBlock>,
+		QueryKind = ValueQuery,
+	>;
+
+	/// The block from which a kitty can be trained again.
+	#[pallet::storage]
+	pub(super) type TrainingCooldowns<T: Config> =
+		StorageMap<Key = [u8; 32], Value = BlockNumberFor<T>>;
+
+	#[pallet::storage]
+	pub(super) type MintCommits<T: Config> = StorageMap<Key = T::AccountId, Value = MintCommit<T>>;
+
+	/// The accounts whose mint commit is revealed at the start of a given block.
+	#[pallet::storage]
+	pub(super) type MintCommitQueue<T: Config> = StorageMap<
+		Key = BlockNumberFor<T>,
+		Value = BoundedVec<T::AccountId, T::MaxCommitsPerBlock>,
+		QueryKind = ValueQuery,
+	>;
+
+	/// The open swaps, keyed by the offered kitty.
+	#[pallet::storage]
+	pub(super) type Swaps<T: Config> = StorageMap<Key = [u8; 32], Value = Swap<T>>;
+
+	/// The offered kitties of every swap asking for a given kitty.
+	#[pallet::storage]
+	pub(super) type SwapsForKitty<T: Config> = StorageMap<
+		Key = [u8; 32],
+		Value = BoundedVec<[u8; 32], T::MaxSwapsPerKitty>,
+		QueryKind = ValueQuery,
+	>;
+
+	#[pallet::storage]
+	pub(super) type Stakes<T: Config> = StorageMap<Key = [u8; 32], Value = Stake<T>>;
+
+	#[pallet::storage]
+	pub(super) type TotalStakeWeight<T: Config> = StorageValue<Value = u128, QueryKind = ValueQuery>;
+
+	/// The rewards accrued per unit of stake weight since genesis, scaled by `REWARD_PRECISION`.
+	#[pallet::storage]
+	pub(super) type RewardPerWeight<T: Config> = StorageValue<Value = u128, QueryKind = ValueQuery>;
+
+	#[pallet::storage]
+	pub(super) type LastRewardBlock<T: Config> =
+		StorageValue<Value = BlockNumberFor<T>, QueryKind = ValueQuery>;
+
+	/// The total supply of shares for every fractionalized kitty.
+	#[pallet::storage]
+	pub(super) type Fractions<T: Config> = StorageMap<Key = [u8; 32], Value = u128>;
+
+	#[pallet::storage]
+	pub(super) type Shares<T: Config> = StorageDoubleMap<
+		Key1 = [u8; 32],
+		Key2 = T::AccountId,
+		Value = u128,
+		QueryKind = ValueQuery,
+	>;
+
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
 		Created { owner: T::AccountId },
 		Transferred { from: T::AccountId, to: T::AccountId, kitty_id: [u8; 32] },
-		PriceSet { owner: T::AccountId, kitty_id: [u8; 32], new_price: Option<BalanceOf<T>> },
-		Sold { buyer: T::AccountId, kitty_id: [u8; 32], price: BalanceOf<T> },
+		PriceSet {
+			owner: T::AccountId,
+			kitty_id: [u8; 32],
+			new_price: Option<BalanceOf<T>>,
+			expires_at: Option<BlockNumberFor<T>>,
+			asset_id: Option<AssetIdOf<T>>,
+		},
+		Sold {
+			buyer: T::AccountId,
+			kitty_id: [u8; 32],
+			price: BalanceOf<T>,
+			asset_id: Option<AssetIdOf<T>>,
+		},
+		LendOffered {
+			lender: T::AccountId,
+			borrower: T::AccountId,
+			kitty_id: [u8; 32],
+			duration: BlockNumberFor<T>,
+			fee: BalanceOf<T>,
+		},
+		LendCancelled { lender: T::AccountId, kitty_id: [u8; 32] },
+		Lent {
+			lender: T::AccountId,
+			borrower: T::AccountId,
+			kitty_id: [u8; 32],
+			expires: BlockNumberFor<T>,
+		},
+		LendingExpired { borrower: T::AccountId, kitty_id: [u8; 32] },
+		Fractionalized { owner: T::AccountId, kitty_id: [u8; 32], shares: u128 },
+		SharesTransferred {
+			from: T::AccountId,
+			to: T::AccountId,
+			kitty_id: [u8; 32],
+			amount: u128,
+		},
+		Reclaimed { owner: T::AccountId, kitty_id: [u8; 32] },
+		Trained { kitty_id: [u8; 32], xp: u32, level: u32 },
+		LevelUp { kitty_id: [u8; 32], level: u32 },
+		MintCommitted { who: T::AccountId, target: BlockNumberFor<T> },
+		MintRefunded { who: T::AccountId, refund: BalanceOf<T> },
+		SwapCreated {
+			creator: T::AccountId,
+			offered_kitty: [u8; 32],
+			desired: SwapTarget,
+			top_up: Option<BalanceOf<T>>,
+		},
+		SwapClaimed {
+			creator: T::AccountId,
+			claimer: T::AccountId,
+			offered_kitty: [u8; 32],
+			received_kitty: [u8; 32],
+		},
+		SwapCancelled { creator: T::AccountId, offered_kitty: [u8; 32] },
+		Staked { owner: T::AccountId, kitty_id: [u8; 32], weight: u128 },
+		Unstaked { owner: T::AccountId, kitty_id: [u8; 32] },
+		RewardsClaimed { owner: T::AccountId, kitty_id: [u8; 32], amount: BalanceOf<T> },
+		ListingExpired { owner: T::AccountId, kitty_id: [u8; 32] },
+		TournamentCreated {
+			tournament_id: u32,
+			organizer: T::AccountId,
+			entry_fee: BalanceOf<T>,
+			size: u32,
+		},
+		TournamentJoined { tournament_id: u32, player: T::AccountId, kitty_id: [u8; 32] },
+		TournamentCancelled { tournament_id: u32 },
+		MatchResolved { tournament_id: u32, winner: [u8; 32], loser: [u8; 32] },
+		TournamentWon {
+			tournament_id: u32,
+			player: T::AccountId,
+			kitty_id: [u8; 32],
+			prize: BalanceOf<T>,
+		},
+		DutchAuctionCreated {
+			owner: T::AccountId,
+			kitty_id: [u8; 32],
+			start_price: BalanceOf<T>,
+			end_price: BalanceOf<T>,
+			start_block: BlockNumberFor<T>,
+			end_block: BlockNumberFor<T>,
+		},
+		PauseSet { paused: bool },
+		GiftCreated { gifter: T::AccountId, kitty_id: [u8; 32], expires: BlockNumberFor<T> },
+		GiftClaimed { gifter: T::AccountId, recipient: T::AccountId, kitty_id: [u8; 32] },
+		GiftReclaimed { gifter: T::AccountId, kitty_id: [u8; 32] },
+		Burned { owner: T::AccountId, kitty_id: [u8; 32] },
+		Fused {
+			owner: T::AccountId,
+			consumed: BoundedVec<[u8; 32], T::MaxFusionInputs>,
+			target: BlockNumberFor<T>,
+		},
+		MintRefundFailed { who: T::AccountId },
+		MintRevealed { who: T::AccountId, kitty_id: [u8; 32] },
+		RewardsForfeited { owner: T::AccountId, kitty_id: [u8; 32], amount: BalanceOf<T> },
 	}
 
 	#[pallet::error]
@@ -68,6 +538,84 @@ pub mod pallet {
 		NotOwner,
 		NotForSale,
 		MaxPriceTooLow,
+		KittyLent,
+		NotLent,
+		NotBorrower,
+		AlreadyBorrowed,
+		InvalidDuration,
+		MaxFeeTooLow,
+		TooManyLendingsExpiring,
+		KittyFractionalized,
+		NotFractionalized,
+		InvalidShares,
+		InsufficientShares,
+		TrainingCooldown,
+		CommitPending,
+		NoCommit,
+		RevealTooEarly,
+		TooManyCommits,
+		SwapExists,
+		NoSwap,
+		InvalidSwap,
+		SwapMismatch,
+		TooManySwaps,
+		KittyStaked,
+		NotStaked,
+		InvalidExpiry,
+		TooManyListingsExpiring,
+		UnknownAsset,
+		InvalidTournamentSize,
+		TooManyTournaments,
+		NoTournament,
+		TournamentFull,
+		TournamentNotFull,
+		TournamentNotReady,
+		KittyInTournament,
+		InvalidAuction,
+		KittyGifted,
+		NoGift,
+		GiftExpired,
+		GiftNotExpired,
+		InvalidGiftClaim,
+		InvalidFusion,
+		KittyInSwap,
+		RegistrationClosed,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(n: BlockNumberFor<T>) -> Weight {
+			FreeMintsInBlock::<T>::kill();
+			Self::expire_lendings(n)
+				.saturating_add(Self::reveal_mint_commits(n))
+				.saturating_add(T::DbWeight::get().writes(1))
+		}
+
+		fn on_idle(n: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+			Self::expire_listings(n, remaining_weight)
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		/// Gift claims are the only unsigned calls, accepted when signed by the claim key.
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let Call::claim_gift { kitty_id, claim_pubkey, signature, recipient } = call else {
+				return InvalidTransaction::Call.into()
+			};
+			let gift = Self::check_gift_claim(kitty_id, claim_pubkey, signature, recipient)
+				.map_err(|_| InvalidTransaction::BadProof)?;
+			let now = frame_system::Pallet::<T>::block_number();
+			let longevity = gift.expires.saturating_sub(now).saturated_into::<u64>();
+
+			ValidTransaction::with_tag_prefix("CollectablesGift")
+				.and_provides(kitty_id)
+				.longevity(longevity)
+				.propagate(true)
+				.build()
+		}
 	}
 
 	#[pallet::call]
@@ -93,9 +641,11 @@ pub mod pallet {
 			origin: OriginFor<T>,
 			kitty_id: [u8; 32],
 			new_price: Option<BalanceOf<T>>,
+			expires_at: Option<BlockNumberFor<T>>,
+			asset_id: Option<AssetIdOf<T>>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			Self::do_set_price(who, kitty_id, new_price)?;
+			Self::do_set_price(who, kitty_id, new_price, expires_at, asset_id)?;
 			Ok(())
 		}
 
@@ -108,5 +658,233 @@ pub mod pallet {
 			Self::do_buy_kitty(who, kitty_id, max_price)?;
 			Ok(())
 		}
+
+		pub fn lend(
+			origin: OriginFor<T>,
+			kitty_id: [u8; 32],
+			borrower: T::AccountId,
+			duration: BlockNumberFor<T>,
+			fee: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_lend(who, kitty_id, borrower, duration, fee)?;
+			Ok(())
+		}
+
+		pub fn cancel_lend(origin: OriginFor<T>, kitty_id: [u8; 32]) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_cancel_lend(who, kitty_id)?;
+			Ok(())
+		}
+
+		pub fn accept_lend(
+			origin: OriginFor<T>,
+			kitty_id: [u8; 32],
+			max_fee: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_accept_lend(who, kitty_id, max_fee)?;
+			Ok(())
+		}
+
+		pub fn fractionalize(
+			origin: OriginFor<T>,
+			kitty_id: [u8; 32],
+			shares: u128,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_fractionalize(who, kitty_id, shares)?;
+			Ok(())
+		}
+
+		pub fn transfer_shares(
+			origin: OriginFor<T>,
+			kitty_id: [u8; 32],
+			to: T::AccountId,
+			amount: u128,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_transfer_shares(who, kitty_id, to, amount)?;
+			Ok(())
+		}
+
+		pub fn reclaim(origin: OriginFor<T>, kitty_id: [u8; 32]) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_reclaim(who, kitty_id)?;
+			Ok(())
+		}
+
+		pub fn train(origin: OriginFor<T>, kitty_id: [u8; 32]) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_train(who, kitty_id)?;
+			Ok(())
+		}
+
+		pub fn commit_mint(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_commit_mint(who)?;
+			Ok(())
+		}
+
+		pub fn create_swap(
+			origin: OriginFor<T>,
+			offered_kitty: [u8; 32],
+			desired: SwapTarget,
+			top_up: Option<BalanceOf<T>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_create_swap(who, offered_kitty, desired, top_up)?;
+			Ok(())
+		}
+
+		pub fn claim_swap(
+			origin: OriginFor<T>,
+			offered_kitty: [u8; 32],
+			kitty_id: [u8; 32],
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_claim_swap(who, offered_kitty, kitty_id)?;
+			Ok(())
+		}
+
+		pub fn cancel_swap(origin: OriginFor<T>, offered_kitty: [u8; 32]) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_cancel_swap(who, offered_kitty)?;
+			Ok(())
+		}
+
+		pub fn stake(origin: OriginFor<T>, kitty_id: [u8; 32]) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_stake(who, kitty_id)?;
+			Ok(())
+		}
+
+		pub fn claim_rewards(origin: OriginFor<T>, kitty_id: [u8; 32]) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_claim_rewards(who, kitty_id)?;
+			Ok(())
+		}
+
+		pub fn unstake(origin: OriginFor<T>, kitty_id: [u8; 32]) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_unstake(who, kitty_id)?;
+			Ok(())
+		}
+
+		pub fn fund_rewards(origin: OriginFor<T>, amount: BalanceOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_fund_rewards(who, amount)?;
+			Ok(())
+		}
+
+		pub fn create_tournament(
+			origin: OriginFor<T>,
+			entry_fee: BalanceOf<T>,
+			size: u32,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_create_tournament(who, entry_fee, size)?;
+			Ok(())
+		}
+
+		pub fn join_tournament(
+			origin: OriginFor<T>,
+			tournament_id: u32,
+			kitty_id: [u8; 32],
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_join_tournament(who, tournament_id, kitty_id)?;
+			Ok(())
+		}
+
+		pub fn cancel_tournament(origin: OriginFor<T>, tournament_id: u32) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_cancel_tournament(who, tournament_id)?;
+			Ok(())
+		}
+
+		pub fn resolve_tournament(origin: OriginFor<T>, tournament_id: u32) -> DispatchResult {
+			ensure_signed(origin)?;
+			Self::do_resolve_tournament(tournament_id)?;
+			Ok(())
+		}
+
+		pub fn create_dutch_auction(
+			origin: OriginFor<T>,
+			kitty_id: [u8; 32],
+			start_price: BalanceOf<T>,
+			end_price: BalanceOf<T>,
+			start_block: BlockNumberFor<T>,
+			end_block: BlockNumberFor<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_create_dutch_auction(
+				who,
+				kitty_id,
+				start_price,
+				end_price,
+				start_block,
+				end_block,
+			)?;
+			Ok(())
+		}
+
+		pub fn set_paused(origin: OriginFor<T>, paused: bool) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Paused::<T>::put(paused);
+			Self::deposit_event(Event::<T>::PauseSet { paused });
+			Ok(())
+		}
+
+		pub fn create_gift(
+			origin: OriginFor<T>,
+			kitty_id: [u8; 32],
+			claim_pubkey_hash: [u8; 32],
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_create_gift(who, kitty_id, claim_pubkey_hash)?;
+			Ok(())
+		}
+
+		/// Claims a gift for `recipient`, who needs neither funds nor an existing account: the
+		/// call is unsigned and authorized by `signature`, the claim key signing the recipient.
+		pub fn claim_gift(
+			origin: OriginFor<T>,
+			kitty_id: [u8; 32],
+			claim_pubkey: T::GiftPublic,
+			signature: T::GiftSignature,
+			recipient: T::AccountId,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			Self::do_claim_gift(kitty_id, claim_pubkey, signature, recipient)?;
+			Ok(())
+		}
+
+		pub fn reclaim_gift(origin: OriginFor<T>, kitty_id: [u8; 32]) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_reclaim_gift(who, kitty_id)?;
+			Ok(())
+		}
+
+		pub fn burn(origin: OriginFor<T>, kitty_id: [u8; 32]) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_burn(who, kitty_id)?;
+			Ok(())
+		}
+
+		pub fn fuse(
+			origin: OriginFor<T>,
+			kitty_ids: BoundedVec<[u8; 32], T::MaxFusionInputs>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_fuse(who, kitty_ids)?;
+			Ok(())
+		}
+
+		pub fn claim_mint_refund(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_claim_mint_refund(who)?;
+			Ok(())
+		}
 	}
 }
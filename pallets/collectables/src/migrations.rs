@@ -0,0 +1,91 @@
+use super::*;
+use frame::deps::frame_support::migrations::VersionedMigration;
+use frame::prelude::*;
+use frame::traits::UncheckedOnRuntimeUpgrade;
+
+pub mod v1 {
+	use super::*;
+
+	/// The `Kitty` layout before `level` and `xp` were introduced.
+	#[derive(Encode, Decode)]
+	pub struct OldKitty<T: Config> {
+		pub dna: [u8; 32],
+		pub owner: T::AccountId,
+		pub price: Option<BalanceOf<T>>,
+	}
+
+	pub struct InnerMigrateV0ToV1<T>(core::marker::PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateV0ToV1<T> {
+		/// Gives every existing kitty the starting level and no experience.
+		fn on_runtime_upgrade() -> Weight {
+			let mut translated = 0u64;
+			Kitties::<T>::translate::<OldKitty<T>, _>(|_, old| {
+				translated += 1;
+				Some(Kitty { dna: old.dna, owner: old.owner, price: old.price, level: 1, xp: 0 })
+			});
+			T::DbWeight::get().reads_writes(translated, translated)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<alloc::vec::Vec<u8>, frame::try_runtime::TryRuntimeError> {
+			Ok(CountForKitties::<T>::get().encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(
+			state: alloc::vec::Vec<u8>,
+		) -> Result<(), frame::try_runtime::TryRuntimeError> {
+			let count = u32::decode(&mut &state[..]).map_err(|_| "invalid pre-upgrade state")?;
+			ensure!(Kitties::<T>::iter().count() as u32 == count, "kitties lost in migration");
+			Ok(())
+		}
+	}
+
+	/// Migrates `Kitties` from storage version 0 to 1, adding `level` and `xp` to every kitty.
+	pub type MigrateV0ToV1<T> = VersionedMigration<
+		0,
+		1,
+		InnerMigrateV0ToV1<T>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+}
+
+pub mod v2 {
+	use super::*;
+	use crate::rarity::TRAIT_SLOTS;
+
+	pub struct InnerMigrateV1ToV2<T>(core::marker::PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateV1ToV2<T> {
+		/// Counts the traits of every existing kitty.
+		fn on_runtime_upgrade() -> Weight {
+			let mut counted = 0u64;
+			for kitty in Kitties::<T>::iter_values() {
+				counted += 1;
+				Pallet::<T>::add_traits(&kitty.dna);
+			}
+			let slots = TRAIT_SLOTS as u64;
+			T::DbWeight::get().reads_writes(counted * (slots + 1), counted * slots)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(
+			_state: alloc::vec::Vec<u8>,
+		) -> Result<(), frame::try_runtime::TryRuntimeError> {
+			let counted: u32 = TraitCounts::<T>::iter_prefix_values(0).sum();
+			ensure!(counted == CountForKitties::<T>::get(), "traits not counted for every kitty");
+			Ok(())
+		}
+	}
+
+	/// Migrates from storage version 1 to 2, filling `TraitCounts` from the existing kitties.
+	pub type MigrateV1ToV2<T> = VersionedMigration<
+		1,
+		2,
+		InnerMigrateV1ToV2<T>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+}
@@ -0,0 +1,113 @@
+use super::*;
+use alloc::vec::Vec;
+use frame::traits::Saturating;
+
+/// The number of leading DNA bytes that are traits, the value of each byte being a trait.
+pub const TRAIT_SLOTS: usize = 8;
+
+/// The most kitties, or owners, a single page of a rarity query reads.
+pub const MAX_RARITY_PAGE: u32 = 256;
+
+/// A page of kitties with their rarity score, and the kitty to continue after.
+pub type RarityPage = (Vec<([u8; 32], u128)>, Option<[u8; 32]>);
+
+/// Scales rarity scores so that traits shared by most kitties still score above zero.
+const RARITY_PRECISION: u128 = 1_000;
+
+impl<T: Config> Pallet<T> {
+	/// Counts the traits of a kitty that was just minted.
+	pub(crate) fn add_traits(dna: &[u8; 32]) {
+		for (slot, value) in dna[..TRAIT_SLOTS].iter().enumerate() {
+			TraitCounts::<T>::mutate(slot as u8, value, |count| count.saturating_inc());
+		}
+	}
+
+	/// Uncounts the traits of a kitty that was just burned.
+	pub(crate) fn remove_traits(dna: &[u8; 32]) {
+		for (slot, value) in dna[..TRAIT_SLOTS].iter().enumerate() {
+			TraitCounts::<T>::mutate_exists(slot as u8, value, |count| {
+				*count = count.map(|count| count.saturating_sub(1)).filter(|count| *count > 0);
+			});
+		}
+	}
+
+	/// The rarity score of a DNA: the sum over its traits of the number of kitties divided by
+	/// the number of kitties sharing that trait. Higher is rarer.
+	pub fn rarity_score(dna: &[u8; 32]) -> u128 {
+		let total = CountForKitties::<T>::get() as u128;
+		dna[..TRAIT_SLOTS]
+			.iter()
+			.enumerate()
+			.map(|(slot, value)| {
+				let count = TraitCounts::<T>::get(slot as u8, value).max(1) as u128;
+				total.saturating_mul(RARITY_PRECISION) / count
+			})
+			.fold(0, u128::saturating_add)
+	}
+
+	/// The kitties among at most `limit` kitties following `start_after` in storage order, with
+	/// their rarity score, rarest first. Also returns the kitty to continue after, or `None` once
+	/// every kitty was read. Callers merge the pages to rank all kitties.
+	///
+	/// Reads up to `MAX_RARITY_PAGE` kitties, and `TRAIT_SLOTS` trait counts for each.
+	pub fn rarest_kitties(
+		start_after: Option<[u8; 32]>,
+		limit: u32,
+	) -> RarityPage {
+		let limit = limit.min(MAX_RARITY_PAGE) as usize;
+		let kitties = match start_after {
+			Some(kitty_id) => Kitties::<T>::iter_from(Kitties::<T>::hashed_key_for(kitty_id)),
+			None => Kitties::<T>::iter(),
+		};
+		let mut scores: Vec<([u8; 32], u128)> = kitties
+			.take(limit)
+			.map(|(kitty_id, kitty)| (kitty_id, Self::rarity_score(&kitty.dna)))
+			.collect();
+		let next = scores.last().map(|(kitty_id, _)| *kitty_id).filter(|_| scores.len() == limit);
+		scores.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+		(scores, next)
+	}
+
+	/// The number of owners, among at most `limit` owners following `start_after` in storage
+	/// order, whose kitties have a higher summed rarity score than those of `owner`. Also
+	/// returns the owner to continue after, or `None` once every owner was read. The rank of
+	/// `owner`, starting at 1, is one more than the sum over all pages. `None` if `owner` has no
+	/// kitty.
+	///
+	/// Reads up to `MAX_RARITY_PAGE` owners, and every kitty of each with its trait counts.
+	pub fn owners_rarer_than(
+		owner: &T::AccountId,
+		start_after: Option<T::AccountId>,
+		limit: u32,
+	) -> Option<(u32, Option<T::AccountId>)> {
+		let score_of = |kitties: &[[u8; 32]]| {
+			kitties
+				.iter()
+				.filter_map(Kitties::<T>::get)
+				.map(|kitty| Self::rarity_score(&kitty.dna))
+				.fold(0, u128::saturating_add)
+		};
+		let owned = KittiesOwned::<T>::get(owner);
+		if owned.is_empty() {
+			return None
+		}
+		let score = score_of(&owned);
+
+		let limit = limit.min(MAX_RARITY_PAGE) as usize;
+		let owners = match start_after {
+			Some(account) => {
+				KittiesOwned::<T>::iter_from(KittiesOwned::<T>::hashed_key_for(account))
+			},
+			None => KittiesOwned::<T>::iter(),
+		};
+		let (mut read, mut ahead, mut last) = (0, 0, None);
+		for (account, kitties) in owners.take(limit) {
+			read += 1;
+			if score_of(&kitties) > score {
+				ahead += 1;
+			}
+			last = Some(account);
+		}
+		Some((ahead, last.filter(|_| read == limit)))
+	}
+}
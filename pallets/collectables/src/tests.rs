@@ -13,11 +13,21 @@
 
 use crate as pallet_kitties;
 use crate::*;
+use frame::deps::frame_support::dispatch::DispatchInfo;
 use frame::deps::frame_support::runtime;
+use frame::deps::frame_system::CheckWeight;
 use frame::deps::sp_io;
+use frame::deps::sp_runtime::testing::TestSignature;
+use frame::deps::sp_runtime::testing::UintAuthorityId;
+use frame::deps::sp_runtime::traits::SignedExtension;
+use frame::primitives::BlakeTwo256;
+use frame::primitives::H256;
 use frame::runtime::prelude::*;
 use frame::testing_prelude::*;
 use frame::traits::fungible::*;
+use frame::traits::fungibles::Mutate as _;
+use frame::traits::AsEnsureOriginWithArg;
+use frame::traits::Hash;
 
 type Balance = u64;
 type Block = frame_system::mocking::MockBlock<TestRuntime>;
@@ -29,6 +39,11 @@ type Block = frame_system::mocking::MockBlock<TestRuntime>;
 const ALICE: u64 = 1;
 const BOB: u64 = 2;
 
+parameter_types! {
+	pub const KittiesPalletId: PalletId = PalletId(*b"py/kitty");
+	pub static MarketplaceFee: Permill = Permill::zero();
+}
+
 #[runtime]
 mod runtime {
 	#[runtime::derive(
@@ -58,6 +73,10 @@ mod runtime {
 	/// PalletKitties: The pallet you are building in this tutorial!
 	#[runtime::pallet_index(2)]
 	pub type PalletKitties = pallet_kitties::Pallet<Runtime>;
+
+	/// PalletAssets: Manages fungible assets that kitties can be priced in.
+	#[runtime::pallet_index(3)]
+	pub type PalletAssets = pallet_assets::Pallet<Runtime>;
 }
 
 // Normally `System` would have many more configurations, but you can see that we use some macro
@@ -76,10 +95,48 @@ impl pallet_balances::Config for TestRuntime {
 	type Balance = Balance;
 }
 
+#[derive_impl(pallet_assets::config_preludes::TestDefaultConfig)]
+impl pallet_assets::Config for TestRuntime {
+	type Balance = Balance;
+	type Currency = PalletBalances;
+	type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<u64>>;
+	type ForceOrigin = frame_system::EnsureRoot<u64>;
+	type Freezer = ();
+}
+
 // This is the configuration of our Pallet! If you make changes to the pallet's `trait Config`, you
 // will also need to update this configuration to represent that.
 impl pallet_kitties::Config for TestRuntime {
 	type RuntimeEvent = RuntimeEvent;
+	type NativeBalance = PalletBalances;
+	type Assets = PalletAssets;
+	type MaxLendingsPerBlock = ConstU32<10>;
+	type TrainCooldown = ConstU64<10>;
+	type XpPerTrain = ConstU32<50>;
+	type XpPerLevel = ConstU32<100>;
+	type MaxLevel = ConstU32<10>;
+	type PalletId = KittiesPalletId;
+	type MintPrice = ConstU64<10>;
+	type RevealDelay = ConstU64<2>;
+	type MaxCommitsPerBlock = ConstU32<10>;
+	type RewardPerBlock = ConstU64<8>;
+	type MaxListingExpiriesPerBlock = ConstU32<10>;
+	type MaxOwnershipHistory = ConstU32<2>;
+	type MaxTournamentSize = ConstU32<8>;
+	type XpPerWin = ConstU32<20>;
+	type AdminOrigin = frame_system::EnsureRoot<u64>;
+	type GiftPublic = UintAuthorityId;
+	type GiftSignature = TestSignature;
+	type GiftExpiry = ConstU64<10>;
+	type MaxFusionInputs = ConstU32<4>;
+	type MarketplaceFee = MarketplaceFee;
+	type FeeDestination = ConstU64<99>;
+	type MaxFreeMintsPerBlock = ConstU32<2>;
+	type FreeMintLongevity = ConstU64<4>;
+	type SwapDeposit = ConstU64<5>;
+	type MaxSwapsPerKitty = ConstU32<16>;
+	type RegistrationPeriod = ConstU64<20>;
+	type TournamentSeedDelay = ConstU64<2>;
 }
 
 // We need to run most of our tests using this function: `new_test_ext().execute_with(|| { ... });`
@@ -115,3 +172,857 @@ fn system_and_balances_work() {
 		assert_ok!(PalletBalances::mint_into(&BOB, 100));
 	});
 }
+
+#[test]
+fn lending_blocks_transfers_until_expiry() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletBalances::mint_into(&ALICE, 100));
+		assert_ok!(PalletBalances::mint_into(&BOB, 100));
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		let kitty_id = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+
+		assert_ok!(PalletKitties::lend(RuntimeOrigin::signed(ALICE), kitty_id, BOB, 5, 10));
+		assert_noop!(
+			PalletKitties::accept_lend(RuntimeOrigin::signed(BOB), kitty_id, 9),
+			Error::<TestRuntime>::MaxFeeTooLow
+		);
+		assert_ok!(PalletKitties::accept_lend(RuntimeOrigin::signed(BOB), kitty_id, 10));
+		assert_eq!(PalletBalances::balance(&ALICE), 110);
+		assert_eq!(PalletKitties::user_of(kitty_id), Some(BOB));
+		assert_noop!(
+			PalletKitties::transfer(RuntimeOrigin::signed(ALICE), BOB, kitty_id),
+			Error::<TestRuntime>::KittyLent
+		);
+
+		System::set_block_number(6);
+		PalletKitties::on_initialize(6);
+		System::assert_last_event(
+			Event::<TestRuntime>::LendingExpired { borrower: BOB, kitty_id }.into(),
+		);
+		assert_eq!(PalletKitties::user_of(kitty_id), Some(ALICE));
+		assert_ok!(PalletKitties::transfer(RuntimeOrigin::signed(ALICE), BOB, kitty_id));
+	});
+}
+
+#[test]
+fn pending_lend_can_be_cancelled() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		let kitty_id = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+		assert_noop!(
+			PalletKitties::lend(RuntimeOrigin::signed(ALICE), kitty_id, BOB, 0, 10),
+			Error::<TestRuntime>::InvalidDuration
+		);
+		assert_ok!(PalletKitties::lend(RuntimeOrigin::signed(ALICE), kitty_id, BOB, 5, 10));
+		assert_ok!(PalletKitties::cancel_lend(RuntimeOrigin::signed(ALICE), kitty_id));
+		assert_noop!(
+			PalletKitties::accept_lend(RuntimeOrigin::signed(BOB), kitty_id, 10),
+			Error::<TestRuntime>::NotLent
+		);
+	});
+}
+
+#[test]
+fn fractionalized_kitty_is_reclaimed_by_full_holder() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		let kitty_id = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+
+		assert_ok!(PalletKitties::fractionalize(RuntimeOrigin::signed(ALICE), kitty_id, 1_000));
+		assert_noop!(
+			PalletKitties::transfer(RuntimeOrigin::signed(ALICE), BOB, kitty_id),
+			Error::<TestRuntime>::KittyFractionalized
+		);
+		assert_noop!(
+			PalletKitties::train(RuntimeOrigin::signed(ALICE), kitty_id),
+			Error::<TestRuntime>::KittyFractionalized
+		);
+		assert_noop!(
+			PalletKitties::transfer_shares(RuntimeOrigin::signed(ALICE), kitty_id, BOB, 0),
+			Error::<TestRuntime>::InvalidShares
+		);
+		assert_ok!(PalletKitties::transfer_shares(
+			RuntimeOrigin::signed(ALICE),
+			kitty_id,
+			BOB,
+			400
+		));
+		assert_noop!(
+			PalletKitties::reclaim(RuntimeOrigin::signed(BOB), kitty_id),
+			Error::<TestRuntime>::InsufficientShares
+		);
+		assert_ok!(PalletKitties::transfer_shares(
+			RuntimeOrigin::signed(ALICE),
+			kitty_id,
+			BOB,
+			600
+		));
+		assert_ok!(PalletKitties::reclaim(RuntimeOrigin::signed(BOB), kitty_id));
+		assert_eq!(Kitties::<TestRuntime>::get(kitty_id).unwrap().owner, BOB);
+		assert_eq!(Shares::<TestRuntime>::get(kitty_id, BOB), 0);
+	});
+}
+
+#[test]
+fn training_levels_up_with_cooldown() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		let kitty_id = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+
+		assert_noop!(
+			PalletKitties::train(RuntimeOrigin::signed(BOB), kitty_id),
+			Error::<TestRuntime>::NotOwner
+		);
+		assert_ok!(PalletKitties::train(RuntimeOrigin::signed(ALICE), kitty_id));
+		assert_noop!(
+			PalletKitties::train(RuntimeOrigin::signed(ALICE), kitty_id),
+			Error::<TestRuntime>::TrainingCooldown
+		);
+
+		System::set_block_number(11);
+		assert_ok!(PalletKitties::train(RuntimeOrigin::signed(ALICE), kitty_id));
+		System::assert_last_event(Event::<TestRuntime>::LevelUp { kitty_id, level: 2 }.into());
+		let kitty = Kitties::<TestRuntime>::get(kitty_id).unwrap();
+		assert_eq!((kitty.level, kitty.xp), (2, 100));
+	});
+}
+
+#[test]
+fn committed_mint_is_revealed_after_target_block() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletBalances::mint_into(&ALICE, 100));
+		assert_ok!(PalletKitties::commit_mint(RuntimeOrigin::signed(ALICE)));
+		assert_eq!(PalletBalances::balance(&ALICE), 90);
+		assert_noop!(
+			PalletKitties::commit_mint(RuntimeOrigin::signed(ALICE)),
+			Error::<TestRuntime>::CommitPending
+		);
+		assert_noop!(
+			PalletKitties::claim_mint_refund(RuntimeOrigin::signed(ALICE)),
+			Error::<TestRuntime>::RevealTooEarly
+		);
+
+		// The DNA is seeded by the hash of block 3, and minted at the start of block 4.
+		let target_hash = H256::repeat_byte(1);
+		frame_system::BlockHash::<TestRuntime>::insert(3, target_hash);
+		System::set_block_number(4);
+		PalletKitties::on_initialize(4);
+		let dna: [u8; 32] = BlakeTwo256::hash_of(&(target_hash, ALICE)).into();
+		System::assert_last_event(
+			Event::<TestRuntime>::MintRevealed { who: ALICE, kitty_id: dna }.into(),
+		);
+		assert_eq!(KittiesOwned::<TestRuntime>::get(ALICE).to_vec(), vec![dna]);
+		assert_eq!(PalletBalances::balance(&ALICE), 90);
+		assert!(!MintCommits::<TestRuntime>::contains_key(ALICE));
+	});
+}
+
+#[test]
+fn unmintable_commit_is_refunded() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletBalances::mint_into(&ALICE, 100));
+		assert_ok!(PalletKitties::commit_mint(RuntimeOrigin::signed(ALICE)));
+		let target_hash = H256::repeat_byte(1);
+		frame_system::BlockHash::<TestRuntime>::insert(3, target_hash);
+		// Another kitty already has the DNA Alice would get.
+		let dna: [u8; 32] = BlakeTwo256::hash_of(&(target_hash, ALICE)).into();
+		assert_ok!(PalletKitties::mint(BOB, dna));
+
+		System::set_block_number(4);
+		PalletKitties::on_initialize(4);
+		System::assert_last_event(
+			Event::<TestRuntime>::MintRefunded { who: ALICE, refund: 10 }.into(),
+		);
+		assert_eq!(PalletBalances::balance(&ALICE), 100);
+		assert!(!MintCommits::<TestRuntime>::contains_key(ALICE));
+	});
+}
+
+#[test]
+fn failed_mint_refund_can_be_claimed_later() {
+	new_test_ext().execute_with(|| {
+		// The hash of the target block is not known, as no block was produced.
+		System::set_block_number(1);
+		assert_ok!(PalletBalances::mint_into(&ALICE, 100));
+		assert_ok!(PalletKitties::commit_mint(RuntimeOrigin::signed(ALICE)));
+		PalletBalances::set_balance(&PalletKitties::account_id(), 0);
+
+		System::set_block_number(4);
+		PalletKitties::on_initialize(4);
+		System::assert_last_event(Event::<TestRuntime>::MintRefundFailed { who: ALICE }.into());
+		assert!(MintCommits::<TestRuntime>::contains_key(ALICE));
+
+		assert_ok!(PalletBalances::mint_into(&PalletKitties::account_id(), 10));
+		assert_ok!(PalletKitties::claim_mint_refund(RuntimeOrigin::signed(ALICE)));
+		System::assert_last_event(
+			Event::<TestRuntime>::MintRefunded { who: ALICE, refund: 10 }.into(),
+		);
+		assert_eq!(PalletBalances::balance(&ALICE), 100);
+		assert!(!MintCommits::<TestRuntime>::contains_key(ALICE));
+	});
+}
+
+#[test]
+fn swap_exchanges_kitties_and_top_up() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletBalances::mint_into(&ALICE, 100));
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(BOB)));
+		let alice_kitty = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+		let bob_kitty = KittiesOwned::<TestRuntime>::get(BOB)[0];
+
+		assert_ok!(PalletKitties::create_swap(
+			RuntimeOrigin::signed(ALICE),
+			alice_kitty,
+			SwapTarget::Kitty(bob_kitty),
+			Some(30)
+		));
+		assert_eq!(PalletBalances::balance(&ALICE), 65);
+		assert_noop!(
+			PalletKitties::stake(RuntimeOrigin::signed(ALICE), alice_kitty),
+			Error::<TestRuntime>::KittyInSwap
+		);
+		assert_noop!(
+			PalletKitties::claim_swap(RuntimeOrigin::signed(BOB), alice_kitty, alice_kitty),
+			Error::<TestRuntime>::NotOwner
+		);
+		assert_ok!(PalletKitties::claim_swap(RuntimeOrigin::signed(BOB), alice_kitty, bob_kitty));
+
+		assert_eq!(Kitties::<TestRuntime>::get(alice_kitty).unwrap().owner, BOB);
+		assert_eq!(Kitties::<TestRuntime>::get(bob_kitty).unwrap().owner, ALICE);
+		assert_eq!(PalletBalances::balance(&BOB), 30);
+		assert_eq!(PalletBalances::balance(&ALICE), 70);
+		assert!(!Swaps::<TestRuntime>::contains_key(alice_kitty));
+	});
+}
+
+#[test]
+fn swap_is_cancelled_when_desired_kitty_changes_hands() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletBalances::mint_into(&ALICE, 100));
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(BOB)));
+		let alice_kitty = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+		let bob_kitty = KittiesOwned::<TestRuntime>::get(BOB)[0];
+
+		assert_ok!(PalletKitties::create_swap(
+			RuntimeOrigin::signed(ALICE),
+			alice_kitty,
+			SwapTarget::Kitty(bob_kitty),
+			Some(30)
+		));
+		assert_ok!(PalletKitties::transfer(RuntimeOrigin::signed(BOB), 3, bob_kitty));
+		assert!(!Swaps::<TestRuntime>::contains_key(alice_kitty));
+		assert_eq!(PalletBalances::balance(&ALICE), 100);
+	});
+}
+
+#[test]
+fn staking_rewards_are_shared_by_rarity() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletBalances::mint_into(&ALICE, 1_000));
+		assert_ok!(PalletKitties::fund_rewards(RuntimeOrigin::signed(ALICE), 500));
+		// A common kitty has weight 1, and a tier 1 kitty has weight 2.
+		assert_ok!(PalletKitties::mint(ALICE, [0xff; 32]));
+		assert_ok!(PalletKitties::mint(BOB, [0x7f; 32]));
+
+		assert_ok!(PalletKitties::stake(RuntimeOrigin::signed(ALICE), [0xff; 32]));
+		assert_ok!(PalletKitties::stake(RuntimeOrigin::signed(BOB), [0x7f; 32]));
+		assert_noop!(
+			PalletKitties::transfer(RuntimeOrigin::signed(BOB), ALICE, [0x7f; 32]),
+			Error::<TestRuntime>::KittyStaked
+		);
+
+		System::set_block_number(4);
+		assert_ok!(PalletKitties::claim_rewards(RuntimeOrigin::signed(BOB), [0x7f; 32]));
+		assert_eq!(PalletBalances::balance(&BOB), 16);
+		assert_ok!(PalletKitties::unstake(RuntimeOrigin::signed(ALICE), [0xff; 32]));
+		assert_eq!(PalletBalances::balance(&ALICE), 508);
+		assert_ok!(PalletKitties::transfer(RuntimeOrigin::signed(ALICE), BOB, [0xff; 32]));
+	});
+}
+
+#[test]
+fn staking_reward_shortfall_stays_owed() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletBalances::mint_into(&ALICE, 1_000));
+		assert_ok!(PalletKitties::fund_rewards(RuntimeOrigin::signed(ALICE), 5));
+		assert_ok!(PalletKitties::mint(ALICE, [0xff; 32]));
+		assert_ok!(PalletKitties::stake(RuntimeOrigin::signed(ALICE), [0xff; 32]));
+
+		// 16 are owed, but the pot only holds 5.
+		System::set_block_number(3);
+		assert_ok!(PalletKitties::claim_rewards(RuntimeOrigin::signed(ALICE), [0xff; 32]));
+		assert_eq!(PalletBalances::balance(&ALICE), 1_000);
+
+		// Once the pot is refilled, the rest is paid.
+		assert_ok!(PalletKitties::fund_rewards(RuntimeOrigin::signed(ALICE), 20));
+		assert_ok!(PalletKitties::claim_rewards(RuntimeOrigin::signed(ALICE), [0xff; 32]));
+		assert_eq!(PalletBalances::balance(&ALICE), 991);
+	});
+}
+
+#[test]
+fn expired_listing_is_not_for_sale_and_cleared() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletBalances::mint_into(&BOB, 100));
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		let kitty_id = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+
+		assert_noop!(
+			PalletKitties::set_price(
+				RuntimeOrigin::signed(ALICE),
+				kitty_id,
+				Some(10),
+				Some(1),
+				None
+			),
+			Error::<TestRuntime>::InvalidExpiry
+		);
+		assert_ok!(PalletKitties::set_price(
+			RuntimeOrigin::signed(ALICE),
+			kitty_id,
+			Some(10),
+			Some(5),
+			None
+		));
+
+		System::set_block_number(5);
+		assert_noop!(
+			PalletKitties::buy_kitty(RuntimeOrigin::signed(BOB), kitty_id, 10),
+			Error::<TestRuntime>::NotForSale
+		);
+		PalletKitties::on_idle(5, Weight::MAX);
+		System::assert_last_event(
+			Event::<TestRuntime>::ListingExpired { owner: ALICE, kitty_id }.into(),
+		);
+		assert_eq!(Kitties::<TestRuntime>::get(kitty_id).unwrap().price, None);
+	});
+}
+
+#[test]
+fn kitty_can_be_bought_with_an_asset() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletAssets::force_create(RuntimeOrigin::root(), 1, ALICE, true, 1));
+		assert_ok!(PalletAssets::mint_into(1, &BOB, 100));
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		let kitty_id = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+
+		assert_noop!(
+			PalletKitties::set_price(
+				RuntimeOrigin::signed(ALICE),
+				kitty_id,
+				Some(40),
+				None,
+				Some(2)
+			),
+			Error::<TestRuntime>::UnknownAsset
+		);
+		assert_ok!(PalletKitties::set_price(
+			RuntimeOrigin::signed(ALICE),
+			kitty_id,
+			Some(40),
+			None,
+			Some(1)
+		));
+		assert_ok!(PalletKitties::buy_kitty(RuntimeOrigin::signed(BOB), kitty_id, 40));
+
+		assert_eq!(PalletAssets::balance(1, ALICE), 40);
+		assert_eq!(PalletAssets::balance(1, BOB), 60);
+		assert_eq!(Kitties::<TestRuntime>::get(kitty_id).unwrap().owner, BOB);
+	});
+}
+
+#[test]
+fn bought_kitty_is_no_longer_for_sale() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletAssets::force_create(RuntimeOrigin::root(), 1, ALICE, true, 1));
+		assert_ok!(PalletAssets::mint_into(1, &BOB, 100));
+		assert_ok!(PalletAssets::mint_into(1, &ALICE, 100));
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		let kitty_id = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+		assert_ok!(PalletKitties::set_price(
+			RuntimeOrigin::signed(ALICE),
+			kitty_id,
+			Some(40),
+			Some(10),
+			Some(1)
+		));
+		assert_ok!(PalletKitties::buy_kitty(RuntimeOrigin::signed(BOB), kitty_id, 40));
+
+		assert_eq!(Kitties::<TestRuntime>::get(kitty_id).unwrap().price, None);
+		assert!(!ListingExpiries::<TestRuntime>::contains_key(kitty_id));
+		assert!(!ListingAssets::<TestRuntime>::contains_key(kitty_id));
+		assert_noop!(
+			PalletKitties::buy_kitty(RuntimeOrigin::signed(ALICE), kitty_id, 40),
+			Error::<TestRuntime>::NotForSale
+		);
+	});
+}
+
+#[test]
+fn ownership_history_keeps_latest_owners() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletBalances::mint_into(&BOB, 100));
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		let kitty_id = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+
+		assert_ok!(PalletKitties::transfer(RuntimeOrigin::signed(ALICE), BOB, kitty_id));
+		System::set_block_number(2);
+		assert_ok!(PalletKitties::transfer(RuntimeOrigin::signed(BOB), ALICE, kitty_id));
+		System::set_block_number(3);
+		assert_ok!(PalletBalances::mint_into(&ALICE, 100));
+		assert_ok!(PalletKitties::set_price(
+			RuntimeOrigin::signed(ALICE),
+			kitty_id,
+			Some(25),
+			None,
+			None
+		));
+		assert_ok!(PalletKitties::buy_kitty(RuntimeOrigin::signed(BOB), kitty_id, 25));
+
+		assert_eq!(
+			PalletKitties::ownership_history(kitty_id),
+			vec![(BOB, 2, None), (ALICE, 3, Some(25))]
+		);
+	});
+}
+
+#[test]
+fn tournament_pays_prize_to_champion() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletBalances::mint_into(&ALICE, 100));
+		assert_ok!(PalletBalances::mint_into(&BOB, 100));
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(BOB)));
+		let alice_kitty = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+		let bob_kitty = KittiesOwned::<TestRuntime>::get(BOB)[0];
+
+		assert_noop!(
+			PalletKitties::create_tournament(RuntimeOrigin::signed(ALICE), 10, 3),
+			Error::<TestRuntime>::InvalidTournamentSize
+		);
+		assert_ok!(PalletKitties::create_tournament(RuntimeOrigin::signed(ALICE), 10, 2));
+		assert_ok!(PalletKitties::join_tournament(RuntimeOrigin::signed(ALICE), 0, alice_kitty));
+		assert_noop!(
+			PalletKitties::resolve_tournament(RuntimeOrigin::signed(BOB), 0),
+			Error::<TestRuntime>::TournamentNotFull
+		);
+		assert_ok!(PalletKitties::join_tournament(RuntimeOrigin::signed(BOB), 0, bob_kitty));
+		assert_noop!(
+			PalletKitties::transfer(RuntimeOrigin::signed(BOB), ALICE, bob_kitty),
+			Error::<TestRuntime>::KittyInTournament
+		);
+
+		System::set_block_number(4);
+		frame_system::BlockHash::<TestRuntime>::insert(3, H256::repeat_byte(3));
+		assert_ok!(PalletKitties::resolve_tournament(RuntimeOrigin::signed(BOB), 0));
+		let alice_won = PalletBalances::balance(&ALICE) == 110;
+		let (winner, player) = if alice_won { (alice_kitty, ALICE) } else { (bob_kitty, BOB) };
+		System::assert_last_event(
+			Event::<TestRuntime>::TournamentWon {
+				tournament_id: 0,
+				player,
+				kitty_id: winner,
+				prize: 20,
+			}
+			.into(),
+		);
+		assert_eq!(Kitties::<TestRuntime>::get(winner).unwrap().xp, 20);
+		assert!(!TournamentEntries::<TestRuntime>::contains_key(alice_kitty));
+		assert_ok!(PalletKitties::transfer(RuntimeOrigin::signed(BOB), ALICE, bob_kitty));
+	});
+}
+
+#[test]
+fn locked_kitty_cannot_join_tournament() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletKitties::mint(ALICE, [0xff; 32]));
+		assert_ok!(PalletKitties::create_tournament(RuntimeOrigin::signed(BOB), 0, 2));
+		assert_ok!(PalletKitties::stake(RuntimeOrigin::signed(ALICE), [0xff; 32]));
+		assert_noop!(
+			PalletKitties::join_tournament(RuntimeOrigin::signed(ALICE), 0, [0xff; 32]),
+			Error::<TestRuntime>::KittyStaked
+		);
+	});
+}
+
+#[test]
+fn stale_tournament_can_be_cancelled_by_anyone() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletBalances::mint_into(&ALICE, 100));
+		assert_ok!(PalletKitties::mint(ALICE, [0xff; 32]));
+		assert_ok!(PalletKitties::create_tournament(RuntimeOrigin::signed(ALICE), 10, 2));
+		assert_ok!(PalletKitties::join_tournament(RuntimeOrigin::signed(ALICE), 0, [0xff; 32]));
+		assert_noop!(
+			PalletKitties::cancel_tournament(RuntimeOrigin::signed(BOB), 0),
+			Error::<TestRuntime>::NotOwner
+		);
+
+		System::set_block_number(21);
+		assert_ok!(PalletKitties::mint(BOB, [0x7f; 32]));
+		assert_noop!(
+			PalletKitties::join_tournament(RuntimeOrigin::signed(BOB), 0, [0x7f; 32]),
+			Error::<TestRuntime>::RegistrationClosed
+		);
+		assert_ok!(PalletKitties::cancel_tournament(RuntimeOrigin::signed(BOB), 0));
+		assert_eq!(PalletBalances::balance(&ALICE), 100);
+		assert!(!TournamentEntries::<TestRuntime>::contains_key([0xff; 32]));
+	});
+}
+
+#[test]
+fn tournament_without_seed_is_refunded() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletBalances::mint_into(&ALICE, 100));
+		assert_ok!(PalletBalances::mint_into(&BOB, 100));
+		assert_ok!(PalletKitties::mint(ALICE, [0xff; 32]));
+		assert_ok!(PalletKitties::mint(BOB, [0x7f; 32]));
+		assert_ok!(PalletKitties::create_tournament(RuntimeOrigin::signed(ALICE), 10, 2));
+		assert_ok!(PalletKitties::join_tournament(RuntimeOrigin::signed(ALICE), 0, [0xff; 32]));
+		assert_ok!(PalletKitties::join_tournament(RuntimeOrigin::signed(BOB), 0, [0x7f; 32]));
+
+		// The hash of the seed block is no longer known, so nobody can win.
+		System::set_block_number(4);
+		assert_ok!(PalletKitties::resolve_tournament(RuntimeOrigin::signed(BOB), 0));
+		System::assert_last_event(
+			Event::<TestRuntime>::TournamentCancelled { tournament_id: 0 }.into(),
+		);
+		assert_eq!(PalletBalances::balance(&ALICE), 100);
+		assert_eq!(PalletBalances::balance(&BOB), 100);
+		assert!(!TournamentEntries::<TestRuntime>::contains_key([0x7f; 32]));
+	});
+}
+
+#[test]
+fn dutch_auction_price_decreases_linearly() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletBalances::mint_into(&BOB, 1_000));
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		let kitty_id = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+
+		assert_noop!(
+			PalletKitties::create_dutch_auction(
+				RuntimeOrigin::signed(ALICE),
+				kitty_id,
+				10,
+				100,
+				2,
+				12
+			),
+			Error::<TestRuntime>::InvalidAuction
+		);
+		assert_ok!(PalletKitties::create_dutch_auction(
+			RuntimeOrigin::signed(ALICE),
+			kitty_id,
+			100,
+			0,
+			2,
+			12
+		));
+		assert_noop!(
+			PalletKitties::buy_kitty(RuntimeOrigin::signed(BOB), kitty_id, 100),
+			Error::<TestRuntime>::NotForSale
+		);
+
+		System::set_block_number(7);
+		assert_noop!(
+			PalletKitties::buy_kitty(RuntimeOrigin::signed(BOB), kitty_id, 49),
+			Error::<TestRuntime>::MaxPriceTooLow
+		);
+		assert_ok!(PalletKitties::buy_kitty(RuntimeOrigin::signed(BOB), kitty_id, 60));
+		assert_eq!(PalletBalances::balance(&BOB), 950);
+		assert_eq!(Kitties::<TestRuntime>::get(kitty_id).unwrap().owner, BOB);
+		assert!(!DutchAuctions::<TestRuntime>::contains_key(kitty_id));
+	});
+}
+
+#[test]
+fn only_admin_can_pause() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_noop!(
+			PalletKitties::set_paused(RuntimeOrigin::signed(ALICE), true),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(PalletKitties::set_paused(RuntimeOrigin::root(), true));
+		assert!(PalletKitties::is_paused());
+		System::assert_last_event(Event::<TestRuntime>::PauseSet { paused: true }.into());
+		assert_ok!(PalletKitties::set_paused(RuntimeOrigin::root(), false));
+		assert!(!PalletKitties::is_paused());
+	});
+}
+
+#[test]
+fn gift_can_be_claimed_with_claim_key() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		let kitty_id = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+		let claim_key = UintAuthorityId(42);
+		let claim_pubkey_hash = BlakeTwo256::hash_of(&claim_key).into();
+		assert_ok!(PalletKitties::create_gift(
+			RuntimeOrigin::signed(ALICE),
+			kitty_id,
+			claim_pubkey_hash
+		));
+		assert_noop!(
+			PalletKitties::transfer(RuntimeOrigin::signed(ALICE), BOB, kitty_id),
+			Error::<TestRuntime>::KittyGifted
+		);
+
+		let payload = (b"claim_gift", kitty_id, BOB).encode();
+		let forged = Call::<TestRuntime>::claim_gift {
+			kitty_id,
+			claim_pubkey: UintAuthorityId(43),
+			signature: TestSignature(43, payload.clone()),
+			recipient: BOB,
+		};
+		assert!(PalletKitties::validate_unsigned(TransactionSource::External, &forged).is_err());
+		let claim = Call::<TestRuntime>::claim_gift {
+			kitty_id,
+			claim_pubkey: claim_key.clone(),
+			signature: TestSignature(42, payload.clone()),
+			recipient: BOB,
+		};
+		assert_ok!(PalletKitties::validate_unsigned(TransactionSource::External, &claim));
+
+		assert_ok!(PalletKitties::claim_gift(
+			RuntimeOrigin::none(),
+			kitty_id,
+			claim_key,
+			TestSignature(42, payload),
+			BOB
+		));
+		assert_eq!(Kitties::<TestRuntime>::get(kitty_id).unwrap().owner, BOB);
+		assert!(!Gifts::<TestRuntime>::contains_key(kitty_id));
+	});
+}
+
+#[test]
+fn expired_gift_can_be_reclaimed() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		let kitty_id = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+		assert_ok!(PalletKitties::create_gift(RuntimeOrigin::signed(ALICE), kitty_id, [0u8; 32]));
+		assert_noop!(
+			PalletKitties::reclaim_gift(RuntimeOrigin::signed(ALICE), kitty_id),
+			Error::<TestRuntime>::GiftNotExpired
+		);
+
+		System::set_block_number(11);
+		assert_noop!(
+			PalletKitties::reclaim_gift(RuntimeOrigin::signed(BOB), kitty_id),
+			Error::<TestRuntime>::NotOwner
+		);
+		assert_ok!(PalletKitties::reclaim_gift(RuntimeOrigin::signed(ALICE), kitty_id));
+		assert_ok!(PalletKitties::transfer(RuntimeOrigin::signed(ALICE), BOB, kitty_id));
+	});
+}
+
+#[test]
+fn rarity_follows_trait_frequencies() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let common = [0u8; 32];
+		let mut common_twin = [0u8; 32];
+		common_twin[31] = 1;
+		let rare = [1u8; 32];
+		assert_ok!(PalletKitties::mint(ALICE, common));
+		assert_ok!(PalletKitties::mint(3, common_twin));
+		assert_ok!(PalletKitties::mint(BOB, rare));
+		assert_eq!(TraitCounts::<TestRuntime>::get(0, 0), 2);
+
+		assert_eq!(PalletKitties::rarity_score(&common), 12_000);
+		let (page, next) = PalletKitties::rarest_kitties(None, 3);
+		assert_eq!(page.len(), 3);
+		assert_eq!(page[0], (rare, 24_000));
+		// The page was full, so the next one starts after its last kitty, and is empty.
+		assert_eq!(PalletKitties::rarest_kitties(next, 3), (vec![], None));
+		let (first, next) = PalletKitties::rarest_kitties(None, 2);
+		let (second, next) = PalletKitties::rarest_kitties(next, 2);
+		assert_eq!((first.len(), second.len(), next), (2, 1, None));
+
+		assert_eq!(PalletKitties::owners_rarer_than(&BOB, None, 10), Some((0, None)));
+		assert_eq!(PalletKitties::owners_rarer_than(&ALICE, None, 10), Some((1, None)));
+
+		assert_ok!(PalletKitties::burn(RuntimeOrigin::signed(BOB), rare));
+		assert!(!TraitCounts::<TestRuntime>::contains_key(0, 1));
+		assert_eq!(CountForKitties::<TestRuntime>::get(), 2);
+		assert_eq!(PalletKitties::owners_rarer_than(&BOB, None, 10), None);
+		assert_eq!(PalletKitties::rarest_kitties(None, 3).0.len(), 2);
+	});
+}
+
+#[test]
+fn fusion_burns_inputs_and_boosts_rarity() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let first = PalletKitties::with_rarity_tier([7u8; 32], 2);
+		let second = [8u8; 32];
+		assert_ok!(PalletKitties::mint(ALICE, first));
+		assert_ok!(PalletKitties::mint(ALICE, second));
+		assert_ok!(PalletKitties::mint(BOB, [9u8; 32]));
+		assert_ok!(PalletKitties::set_price(
+			RuntimeOrigin::signed(ALICE),
+			second,
+			Some(10),
+			None,
+			None
+		));
+
+		let inputs: BoundedVec<_, _> = vec![first, first].try_into().unwrap();
+		assert_noop!(
+			PalletKitties::fuse(RuntimeOrigin::signed(ALICE), inputs),
+			Error::<TestRuntime>::InvalidFusion
+		);
+		let inputs: BoundedVec<_, _> = vec![first, [9u8; 32]].try_into().unwrap();
+		assert_noop!(
+			PalletKitties::fuse(RuntimeOrigin::signed(ALICE), inputs),
+			Error::<TestRuntime>::NotOwner
+		);
+
+		let inputs: BoundedVec<_, _> = vec![first, second].try_into().unwrap();
+		assert_ok!(PalletKitties::fuse(RuntimeOrigin::signed(ALICE), inputs.clone()));
+		System::assert_last_event(
+			Event::<TestRuntime>::Fused { owner: ALICE, consumed: inputs, target: 3 }.into(),
+		);
+		assert!(KittiesOwned::<TestRuntime>::get(ALICE).is_empty());
+		assert_eq!(CountForKitties::<TestRuntime>::get(), 1);
+		assert!(!Kitties::<TestRuntime>::contains_key(first));
+		assert!(!Kitties::<TestRuntime>::contains_key(second));
+		assert_noop!(
+			PalletKitties::commit_mint(RuntimeOrigin::signed(ALICE)),
+			Error::<TestRuntime>::CommitPending
+		);
+
+		// The fused DNA is seeded by the hash of block 3, like a committed mint.
+		frame_system::BlockHash::<TestRuntime>::insert(3, H256::repeat_byte(1));
+		System::set_block_number(4);
+		PalletKitties::on_initialize(4);
+		let owned = KittiesOwned::<TestRuntime>::get(ALICE);
+		assert_eq!(owned.len(), 1);
+		assert!(PalletKitties::rarity_tier(&owned[0]) >= 3);
+		assert_eq!(CountForKitties::<TestRuntime>::get(), 2);
+		assert!(!MintCommits::<TestRuntime>::contains_key(ALICE));
+	});
+}
+
+#[test]
+fn max_level_inputs_raise_fused_rarity() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (first, second) = ([0xffu8; 32], [0xfeu8; 32]);
+		assert_ok!(PalletKitties::mint(ALICE, first));
+		assert_ok!(PalletKitties::mint(ALICE, second));
+		Kitties::<TestRuntime>::mutate(first, |kitty| kitty.as_mut().unwrap().level = 10);
+
+		let inputs: BoundedVec<_, _> = vec![first, second].try_into().unwrap();
+		assert_ok!(PalletKitties::fuse(RuntimeOrigin::signed(ALICE), inputs));
+		assert_eq!(MintCommits::<TestRuntime>::get(ALICE).unwrap().min_tier, 2);
+	});
+}
+
+#[test]
+fn marketplace_fee_is_taken_from_sales() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		MarketplaceFee::set(Permill::from_percent(10));
+		assert_ok!(PalletBalances::mint_into(&ALICE, 10));
+		assert_ok!(PalletBalances::mint_into(&BOB, 1_000));
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		let kitty_id = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+		assert_ok!(PalletKitties::set_price(
+			RuntimeOrigin::signed(ALICE),
+			kitty_id,
+			Some(100),
+			None,
+			None
+		));
+
+		assert_ok!(PalletKitties::buy_kitty(RuntimeOrigin::signed(BOB), kitty_id, 100));
+		assert_eq!(PalletBalances::balance(&BOB), 900);
+		assert_eq!(PalletBalances::balance(&ALICE), 100);
+		assert_eq!(PalletBalances::balance(&99), 10);
+	});
+}
+
+#[test]
+fn fee_below_min_balance_goes_to_seller() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		MarketplaceFee::set(Permill::from_percent(10));
+		assert_ok!(PalletAssets::force_create(RuntimeOrigin::root(), 1, ALICE, true, 10));
+		assert_ok!(PalletAssets::mint_into(1, &BOB, 100));
+		assert_ok!(PalletKitties::create_kitty(RuntimeOrigin::signed(ALICE)));
+		let kitty_id = KittiesOwned::<TestRuntime>::get(ALICE)[0];
+		assert_ok!(PalletKitties::set_price(
+			RuntimeOrigin::signed(ALICE),
+			kitty_id,
+			Some(40),
+			None,
+			Some(1)
+		));
+
+		// The fee of 4 could not endow the fee destination, so the seller keeps it.
+		assert_ok!(PalletKitties::buy_kitty(RuntimeOrigin::signed(BOB), kitty_id, 40));
+		assert_eq!(PalletAssets::balance(1, ALICE), 40);
+		assert_eq!(PalletAssets::balance(1, 99), 0);
+		assert_eq!(PalletAssets::balance(1, BOB), 60);
+	});
+}
+
+#[test]
+fn first_mint_of_each_account_is_free() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let mint: RuntimeCall = Call::create_kitty {}.into();
+		let info = DispatchInfo::default();
+		// `CheckWeight` stands in for the fee payment extension.
+		type Extension = FreeFirstMint<TestRuntime, CheckWeight<TestRuntime>>;
+		let extension = || Extension::from(CheckWeight::<TestRuntime>::new());
+		let free_mint = |who: u64| extension().pre_dispatch(&who, &mint, &info, 0);
+		let post_dispatch = |pre, result: DispatchResult| {
+			Extension::post_dispatch(Some(pre), &info, &Default::default(), 0, &result)
+		};
+
+		// Free mints share `MaxFreeMintsPerBlock` slots of the pool, for a bounded time.
+		let valid = extension().validate(&ALICE, &mint, &info, 0).unwrap();
+		assert_eq!(valid.provides.len(), 2);
+		assert_eq!(valid.longevity, 4);
+
+		let pre = free_mint(ALICE).unwrap();
+		assert_eq!(pre, FreeMintPre::Free(ALICE));
+		// A failed free mint is not used up.
+		assert_ok!(post_dispatch(pre, Err(Error::<TestRuntime>::TooManyOwned.into())));
+		let pre = free_mint(ALICE).unwrap();
+		assert_eq!(pre, FreeMintPre::Free(ALICE));
+		assert_ok!(post_dispatch(pre, Ok(())));
+		// The second mint of an account pays fees as usual.
+		assert_eq!(free_mint(ALICE), Ok(FreeMintPre::Paid(())));
+		// The block quota is used up, so the next free mint waits for the next block.
+		assert_eq!(free_mint(BOB), Err(InvalidTransaction::ExhaustsResources.into()));
+
+		PalletKitties::on_initialize(2);
+		assert_eq!(free_mint(BOB), Ok(FreeMintPre::Free(BOB)));
+	});
+}
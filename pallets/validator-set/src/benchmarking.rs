@@ -0,0 +1,47 @@
+//! Benchmarking setup for pallet-validator-set
+#![cfg(feature = "runtime-benchmarks")]
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as ValidatorSet;
+use frame_benchmarking::v2::*;
+use frame_support::traits::{EnsureOrigin, Get};
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn add_validator() {
+		let origin = T::AddRemoveOrigin::try_successful_origin()
+			.expect("AddRemoveOrigin has no successful origin required for the benchmark");
+		Validators::<T>::kill();
+		for i in 0..T::MaxValidators::get() - 1 {
+			Validators::<T>::try_append(account::<T::AccountId>("validator", i, 0)).unwrap();
+		}
+		let validator: T::AccountId = whitelisted_caller();
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, validator.clone());
+
+		assert!(Validators::<T>::get().contains(&validator));
+	}
+
+	#[benchmark]
+	fn remove_validator() {
+		let origin = T::AddRemoveOrigin::try_successful_origin()
+			.expect("AddRemoveOrigin has no successful origin required for the benchmark");
+		Validators::<T>::kill();
+		for i in 0..T::MaxValidators::get() {
+			Validators::<T>::try_append(account::<T::AccountId>("validator", i, 0)).unwrap();
+		}
+		let validator = account::<T::AccountId>("validator", 0, 0);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, validator.clone());
+
+		assert!(!Validators::<T>::get().contains(&validator));
+	}
+
+	impl_benchmark_test_suite!(ValidatorSet, crate::mock::new_test_ext(), crate::mock::Test);
+}
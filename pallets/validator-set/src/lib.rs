@@ -0,0 +1,183 @@
+//! # Validator Set Pallet
+//!
+//! Keeps the list of validators of the PoA network, so that authorities can be added and removed
+//! without a hard fork.
+//!
+//! ## Overview
+//!
+//! The pallet implements [`pallet_session::SessionManager`]: at every session boundary,
+//! `pallet-session` queues the current list of validators, together with their session keys, as
+//! the Aura and Grandpa authorities of the next session.
+//!
+//! - [`Pallet::add_validator`] adds an account to the validator set.
+//! - [`Pallet::remove_validator`] removes an account from the validator set.
+//!
+//! Both are restricted to [`Config::AddRemoveOrigin`]. A validator only starts producing blocks
+//! once it has registered its session keys with `session.setKeys`, two sessions after being
+//! added.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod weights;
+pub use weights::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use alloc::vec::Vec;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching runtime event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+		/// The origin allowed to add and remove validators.
+		type AddRemoveOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+		/// The maximum number of validators.
+		#[pallet::constant]
+		type MaxValidators: Get<u32>;
+		/// The minimum number of validators, below which removing one is refused.
+		#[pallet::constant]
+		type MinValidators: Get<u32>;
+		/// A type representing the weights required by the dispatchables of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	/// The validators handed to `pallet-session` at the next session boundary.
+	#[pallet::storage]
+	pub type Validators<T: Config> =
+		StorageValue<_, BoundedVec<T::AccountId, T::MaxValidators>, ValueQuery>;
+
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		/// The validators of the first session.
+		pub initial_validators: Vec<T::AccountId>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			let validators: BoundedVec<_, T::MaxValidators> = self
+				.initial_validators
+				.clone()
+				.try_into()
+				.expect("too many initial validators");
+			Validators::<T>::put(validators);
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A validator was added, effective from the next session but one.
+		ValidatorAdded { validator: T::AccountId },
+		/// A validator was removed, effective from the next session but one.
+		ValidatorRemoved { validator: T::AccountId },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The account is already a validator.
+		Duplicate,
+		/// The account is not a validator.
+		NotValidator,
+		/// The validator set is full.
+		TooManyValidators,
+		/// Removing the validator would leave fewer than [`Config::MinValidators`].
+		TooFewValidators,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Adds `validator` to the validator set.
+		///
+		/// The validator takes part in consensus once the session after the next one starts,
+		/// provided it has registered its session keys.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::add_validator())]
+		pub fn add_validator(origin: OriginFor<T>, validator: T::AccountId) -> DispatchResult {
+			T::AddRemoveOrigin::ensure_origin(origin)?;
+
+			Validators::<T>::try_mutate(|validators| {
+				ensure!(!validators.contains(&validator), Error::<T>::Duplicate);
+				validators.try_push(validator.clone()).map_err(|_| Error::<T>::TooManyValidators)
+			})?;
+
+			Self::deposit_event(Event::ValidatorAdded { validator });
+			Ok(())
+		}
+
+		/// Removes `validator` from the validator set.
+		///
+		/// The validator stops taking part in consensus once the session after the next one
+		/// starts.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::WeightInfo::remove_validator())]
+		pub fn remove_validator(origin: OriginFor<T>, validator: T::AccountId) -> DispatchResult {
+			T::AddRemoveOrigin::ensure_origin(origin)?;
+			Self::do_remove_validator(&validator)?;
+
+			Self::deposit_event(Event::ValidatorRemoved { validator });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Removes `validator` from the set, keeping at least [`Config::MinValidators`].
+		pub fn do_remove_validator(validator: &T::AccountId) -> DispatchResult {
+			Validators::<T>::try_mutate(|validators| {
+				ensure!(
+					validators.len() as u32 > T::MinValidators::get(),
+					Error::<T>::TooFewValidators
+				);
+				let index = validators
+					.iter()
+					.position(|v| v == validator)
+					.ok_or(Error::<T>::NotValidator)?;
+				validators.remove(index);
+				Ok(())
+			})
+		}
+	}
+
+	impl<T: Config> pallet_session::SessionManager<T::AccountId> for Pallet<T> {
+		fn new_session(_new_index: u32) -> Option<Vec<T::AccountId>> {
+			let validators = Validators::<T>::get();
+			// An empty set would stall the chain, keep the current authorities instead.
+			(!validators.is_empty()).then(|| validators.into_inner())
+		}
+
+		fn end_session(_end_index: u32) {}
+
+		fn start_session(_start_index: u32) {}
+	}
+
+	/// Validators are identified by their account alone, they have no stake to expose.
+	impl<T: Config> pallet_session::historical::SessionManager<T::AccountId, ()> for Pallet<T> {
+		fn new_session(new_index: u32) -> Option<Vec<(T::AccountId, ())>> {
+			<Self as pallet_session::SessionManager<_>>::new_session(new_index)
+				.map(|validators| validators.into_iter().map(|validator| (validator, ())).collect())
+		}
+
+		fn end_session(_end_index: u32) {}
+
+		fn start_session(_start_index: u32) {}
+	}
+}
@@ -0,0 +1,44 @@
+use crate::{mock::*, Error, Event, Validators};
+use frame_support::{assert_noop, assert_ok};
+use pallet_session::SessionManager;
+use sp_runtime::DispatchError;
+
+#[test]
+fn add_validator_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_noop!(
+			ValidatorSet::add_validator(RuntimeOrigin::signed(1), 3),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			ValidatorSet::add_validator(RuntimeOrigin::root(), 2),
+			Error::<Test>::Duplicate
+		);
+		assert_ok!(ValidatorSet::add_validator(RuntimeOrigin::root(), 3));
+		System::assert_last_event(Event::ValidatorAdded { validator: 3 }.into());
+		assert_eq!(ValidatorSet::new_session(1), Some(vec![1, 2, 3]));
+		assert_noop!(
+			ValidatorSet::add_validator(RuntimeOrigin::root(), 4),
+			Error::<Test>::TooManyValidators
+		);
+	});
+}
+
+#[test]
+fn remove_validator_keeps_minimum() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_noop!(
+			ValidatorSet::remove_validator(RuntimeOrigin::root(), 3),
+			Error::<Test>::NotValidator
+		);
+		assert_ok!(ValidatorSet::remove_validator(RuntimeOrigin::root(), 1));
+		System::assert_last_event(Event::ValidatorRemoved { validator: 1 }.into());
+		assert_eq!(Validators::<Test>::get().into_inner(), vec![2]);
+		assert_noop!(
+			ValidatorSet::remove_validator(RuntimeOrigin::root(), 2),
+			Error::<Test>::TooFewValidators
+		);
+	});
+}
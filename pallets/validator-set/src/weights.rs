@@ -0,0 +1,50 @@
+//! Weights for pallet_validator_set
+//!
+//! Estimated from the storage accessed by each call, until they are replaced by the output of
+//! `benchmark pallet --pallet pallet_validator_set`.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for pallet_validator_set.
+pub trait WeightInfo {
+	fn add_validator() -> Weight;
+	fn remove_validator() -> Weight;
+}
+
+/// Weights for pallet_validator_set using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: ValidatorSet Validators (r:1 w:1)
+	fn add_validator() -> Weight {
+		Weight::from_parts(15_000_000, 3_300)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: ValidatorSet Validators (r:1 w:1)
+	fn remove_validator() -> Weight {
+		Weight::from_parts(15_000_000, 3_300)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	/// Storage: ValidatorSet Validators (r:1 w:1)
+	fn add_validator() -> Weight {
+		Weight::from_parts(15_000_000, 3_300)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: ValidatorSet Validators (r:1 w:1)
+	fn remove_validator() -> Weight {
+		Weight::from_parts(15_000_000, 3_300)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+}
@@ -27,7 +27,20 @@ frame_benchmarking::define_benchmarks!(
 	[frame_benchmarking, BaselineBench::<Runtime>]
 	[frame_system, SystemBench::<Runtime>]
 	[pallet_balances, Balances]
+	[pallet_assets, Assets]
 	[pallet_timestamp, Timestamp]
 	[pallet_sudo, Sudo]
-	[pallet_template, TemplateModule]
+	[pallet_tx_pause, TxPause]
+	[pallet_collective, Council]
+	[pallet_democracy, Democracy]
+	[pallet_scheduler, Scheduler]
+	[pallet_preimage, Preimage]
+	[pallet_treasury, Treasury]
+	[pallet_utility, Utility]
+	[pallet_multisig, Multisig]
+	[pallet_proxy, Proxy]
+	[pallet_contracts, Contracts]
+	[pallet_asset_tx_payment, AssetTxPayment]
+	[pallet_vesting, Vesting]
+	[pallet_validator_set, ValidatorSet]
 );
\ No newline at end of file
@@ -0,0 +1,193 @@
+// This is free and unencumbered software released into the public domain.
+//
+// Anyone is free to copy, modify, publish, use, compile, sell, or
+// distribute this software, either in source code form or as a compiled
+// binary, for any purpose, commercial or non-commercial, and by any
+// means.
+//
+// In jurisdictions that recognize copyright laws, the author or authors
+// of this software dedicate any and all copyright interest in the
+// software to the public domain. We make this dedication for the benefit
+// of the public at large and to the detriment of our heirs and
+// successors. We intend this dedication to be an overt act of
+// relinquishment in perpetuity of all present and future rights to this
+// software under copyright law.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT.
+// IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY CLAIM, DAMAGES OR
+// OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+// ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR
+// OTHER DEALINGS IN THE SOFTWARE.
+//
+// For more information, please refer to <http://unlicense.org>
+
+//! Smart contracts, and the chain extension giving them access to the kitties.
+
+use codec::Encode;
+use frame_support::{
+	parameter_types,
+	traits::{ConstBool, ConstU32, Get, Nothing, PalletInfoAccess},
+	weights::Weight,
+};
+use frame_system::EnsureSigned;
+use pallet_contracts::chain_extension::{
+	BufInBufOutState, ChainExtension, Environment, Ext, InitState, RetVal, SysConfig,
+};
+use sp_runtime::{traits::Dispatchable, DispatchError, ModuleError, Perbill};
+
+use crate::{
+	AccountId, Balance, Balances, Collectables, RandomnessCollectiveFlip, Runtime, RuntimeCall,
+	RuntimeEvent, RuntimeHoldReason, RuntimeOrigin, Timestamp, MILLI_UNIT, UNIT,
+};
+
+const fn deposit(items: u32, bytes: u32) -> Balance {
+	items as Balance * UNIT + (bytes as Balance) * (10 * MILLI_UNIT)
+}
+
+parameter_types! {
+	pub const DepositPerItem: Balance = deposit(1, 0);
+	pub const DepositPerByte: Balance = deposit(0, 1);
+	pub const DefaultDepositLimit: Balance = deposit(1024, 1024 * 1024);
+	pub Schedule: pallet_contracts::Schedule<Runtime> = Default::default();
+	pub CodeHashLockupDepositPercent: Perbill = Perbill::from_percent(30);
+}
+
+/// Only meant for the contracts' `seal_random`, which is deprecated and not used for the kitties.
+impl pallet_insecure_randomness_collective_flip::Config for Runtime {}
+
+impl pallet_contracts::Config for Runtime {
+	type Time = Timestamp;
+	type Randomness = RandomnessCollectiveFlip;
+	type Currency = Balances;
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeCall = RuntimeCall;
+	/// Contracts reach the runtime through [`CollectablesExtension`] only.
+	type CallFilter = Nothing;
+	type DepositPerItem = DepositPerItem;
+	type DepositPerByte = DepositPerByte;
+	type DefaultDepositLimit = DefaultDepositLimit;
+	type CallStack = [pallet_contracts::Frame<Self>; 5];
+	type WeightPrice = pallet_transaction_payment::Pallet<Self>;
+	type WeightInfo = pallet_contracts::weights::SubstrateWeight<Self>;
+	type ChainExtension = CollectablesExtension;
+	type Schedule = Schedule;
+	type AddressGenerator = pallet_contracts::DefaultAddressGenerator;
+	type MaxCodeLen = ConstU32<{ 123 * 1024 }>;
+	type MaxStorageKeyLen = ConstU32<128>;
+	type MaxTransientStorageSize = ConstU32<{ 1024 * 1024 }>;
+	type UnsafeUnstableInterface = ConstBool<false>;
+	type UploadOrigin = EnsureSigned<AccountId>;
+	type InstantiateOrigin = EnsureSigned<AccountId>;
+	type MaxDebugBufferLen = ConstU32<{ 2 * 1024 * 1024 }>;
+	type MaxDelegateDependencies = ConstU32<32>;
+	type RuntimeHoldReason = RuntimeHoldReason;
+	#[cfg(not(feature = "runtime-benchmarks"))]
+	type Migrations = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type Migrations = pallet_contracts::migration::codegen::BenchMigrations;
+	type CodeHashLockupDepositPercent = CodeHashLockupDepositPercent;
+	type Debug = ();
+	type Environment = ();
+	type ApiVersion = ();
+	type Xcm = ();
+}
+
+/// Chain extension letting contracts read kitties and trade the ones they own.
+///
+/// | `func_id` | input                                     | output          |
+/// |-----------|-------------------------------------------|-----------------|
+/// | 1         | `kitty_id: [u8; 32]`                      | `Option<Kitty>` |
+/// | 2         | `(to: AccountId, kitty_id: [u8; 32])`     |                 |
+/// | 3         | `(kitty_id: [u8; 32], Option<Balance>)`   |                 |
+///
+/// Transfers and listings are dispatched as `pallet_collectables` calls signed by the contract, so
+/// they go through the same checks and call filter as calls from any other account.
+///
+/// Every function returns 0 on success. When a transfer or listing fails, its changes are reverted
+/// and the function returns `1 + i` for the `i`th variant of `pallet_collectables::Error`, or
+/// [`OTHER_ERROR`] for any other error, so the contract can handle it. Running out of gas or
+/// passing malformed input still traps the contract.
+#[derive(Default)]
+pub struct CollectablesExtension;
+
+/// Returned by the chain extension when a call fails with an error of another pallet, e.g. for a
+/// balance too low to pay.
+pub const OTHER_ERROR: u32 = 1000;
+
+impl ChainExtension<Runtime> for CollectablesExtension {
+	fn call<E: Ext<T = Runtime>>(
+		&mut self,
+		env: Environment<E, InitState>,
+	) -> Result<RetVal, DispatchError> {
+		let mut env = env.buf_in_buf_out();
+		let code = match env.func_id() {
+			1 => {
+				let kitty_id: [u8; 32] = env.read_as()?;
+				env.charge_weight(<Runtime as SysConfig>::DbWeight::get().reads(1))?;
+				let kitty = pallet_collectables::Pallet::<Runtime>::kitty(kitty_id);
+				env.write(&kitty.encode(), false, None)?;
+				0
+			},
+			2 => {
+				let (to, kitty_id): (AccountId, [u8; 32]) = env.read_as()?;
+				let call = pallet_collectables::Call::transfer { to, kitty_id };
+				Self::dispatch(&mut env, call, Self::transfer_weight())?
+			},
+			3 => {
+				let (kitty_id, new_price): ([u8; 32], Option<Balance>) = env.read_as()?;
+				let call = pallet_collectables::Call::set_price {
+					kitty_id,
+					new_price,
+					expires_at: None,
+					asset_id: None,
+				};
+				Self::dispatch(&mut env, call, Self::set_price_weight())?
+			},
+			_ => return Err(DispatchError::Other("unknown chain extension function")),
+		};
+		Ok(RetVal::Converging(code))
+	}
+}
+
+impl CollectablesExtension {
+	/// The weight of `transfer`, including the cancellation of every swap asking for the kitty.
+	/// `pallet_collectables` is not benchmarked, and its dispatch weight is zero, so this counts
+	/// its storage accesses instead.
+	fn transfer_weight() -> Weight {
+		let db_weight = <Runtime as SysConfig>::DbWeight::get();
+		let max_swaps: u32 = <Runtime as pallet_collectables::Config>::MaxSwapsPerKitty::get();
+		db_weight
+			.reads_writes(12, 9)
+			.saturating_add(db_weight.reads_writes(6, 7).saturating_mul(max_swaps.into()))
+	}
+
+	/// The weight of `set_price`, counted the same way as [`Self::transfer_weight`].
+	fn set_price_weight() -> Weight {
+		<Runtime as SysConfig>::DbWeight::get().reads_writes(9, 6)
+	}
+
+	/// Charges `weight` and dispatches `call` signed by the calling contract, returning the code
+	/// of its outcome.
+	fn dispatch<E: Ext<T = Runtime>>(
+		env: &mut Environment<E, BufInBufOutState>,
+		call: pallet_collectables::Call<Runtime>,
+		weight: Weight,
+	) -> Result<u32, DispatchError> {
+		let call = RuntimeCall::Collectables(call);
+		env.charge_weight(weight)?;
+		let origin = RuntimeOrigin::signed(env.ext().address().clone());
+		Ok(match call.dispatch(origin) {
+			Ok(_) => 0,
+			Err(e) => match e.error {
+				DispatchError::Module(ModuleError { index, error, .. })
+					if index as usize == Collectables::index() =>
+				{
+					1 + error[0] as u32
+				},
+				_ => OTHER_ERROR,
+			},
+		})
+	}
+}
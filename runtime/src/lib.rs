@@ -1,4 +1,6 @@
 #![cfg_attr(not(feature = "std"), no_std)]
+// The runtime declares more pallets than the default limit lets `frame_support::runtime` expand.
+#![recursion_limit = "256"]
 
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
@@ -31,6 +33,39 @@ pub use sp_runtime::BuildStorage;
 impl pallet_collectables::Config for Runtime {
     type RuntimeEvent = RuntimeEvent; // Eventos do runtime
     type NativeBalance = Balances;   // Usando o pallet Balances para gerenciar saldo
+    type Assets = Assets; // Ativos aceitos como pagamento além do token nativo
+    type MaxLendingsPerBlock = frame_support::traits::ConstU32<64>; // Empréstimos que expiram por bloco
+    type TrainCooldown = frame_support::traits::ConstU32<{ HOURS }>; // Intervalo entre treinos
+    type XpPerTrain = frame_support::traits::ConstU32<10>; // XP ganho por treino
+    type XpPerLevel = frame_support::traits::ConstU32<100>; // XP necessário por nível
+    type MaxLevel = frame_support::traits::ConstU32<100>; // Nível máximo
+    type PalletId = CollectablesPalletId; // Conta que guarda os valores em custódia
+    type MintPrice = frame_support::traits::ConstU128<{ 10 * MILLI_UNIT }>; // Preço do mint com commit-reveal
+    type RevealDelay = frame_support::traits::ConstU32<2>; // Blocos até a semente do DNA existir
+    type MaxCommitsPerBlock = frame_support::traits::ConstU32<64>; // Commits que expiram por bloco
+    type RewardPerBlock = frame_support::traits::ConstU128<{ MILLI_UNIT }>; // Recompensa de staking por bloco
+    type MaxListingExpiriesPerBlock = frame_support::traits::ConstU32<64>; // Anúncios que expiram por bloco
+    type MaxOwnershipHistory = frame_support::traits::ConstU32<16>; // Donos anteriores guardados por kitty
+    type MaxTournamentSize = frame_support::traits::ConstU32<16>; // Tamanho máximo do torneio
+    type XpPerWin = frame_support::traits::ConstU32<25>; // XP por vitória
+    type AdminOrigin = configs::EnsureRootOrHalfCouncil; // Quem pode pausar o marketplace
+    type GiftPublic = sp_runtime::MultiSigner; // Chave do link de resgate do presente
+    type GiftSignature = Signature; // Assinatura do destinatário pela chave de resgate
+    type GiftExpiry = frame_support::traits::ConstU32<{ 7 * DAYS }>; // Prazo para resgatar o presente
+    type MaxFusionInputs = frame_support::traits::ConstU32<5>; // Kitties consumidos por fusão
+    type MarketplaceFee = MarketplaceFee; // Parte de cada venda cobrada pelo marketplace
+    type FeeDestination = configs::TreasuryAccount; // A taxa do marketplace vai para a tesouraria
+    type MaxFreeMintsPerBlock = frame_support::traits::ConstU32<10>; // Primeiros mints sem taxa por bloco
+    type FreeMintLongevity = frame_support::traits::ConstU64<5>; // Blocos que um mint grátis espera no pool
+    type SwapDeposit = frame_support::traits::ConstU128<{ 10 * MILLI_UNIT }>; // Depósito por troca aberta
+    type MaxSwapsPerKitty = frame_support::traits::ConstU32<16>; // Trocas abertas pedindo a mesma kitty
+    type RegistrationPeriod = frame_support::traits::ConstU32<{ DAYS }>; // Prazo de inscrição no torneio
+    type TournamentSeedDelay = frame_support::traits::ConstU32<2>; // Blocos até a semente do torneio existir
+}
+
+frame_support::parameter_types! {
+    pub const CollectablesPalletId: frame_support::PalletId = frame_support::PalletId(*b"py/kitty");
+    pub const MarketplaceFee: sp_runtime::Permill = sp_runtime::Permill::from_percent(2);
 }
 
 /// Tipos opacos. Usados pelo CLI para lidar com tipos que não precisam conhecer detalhes da runtime.
@@ -67,10 +102,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_name: create_runtime_str!("solochain-template-runtime"),
     impl_name: create_runtime_str!("solochain-template-runtime"),
     authoring_version: 1,
-    spec_version: 100,
+    spec_version: 103,
     impl_version: 1,
     apis: apis::RUNTIME_API_VERSIONS,
-    transaction_version: 1,
+    transaction_version: 2,
     state_version: 1,
 };
 
@@ -110,7 +145,10 @@ pub type SignedExtra = (
     frame_system::CheckEra<Runtime>,
     frame_system::CheckNonce<Runtime>,
     frame_system::CheckWeight<Runtime>,
-    pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+    pallet_collectables::FreeFirstMint<
+        Runtime,
+        pallet_asset_tx_payment::ChargeAssetTxPayment<Runtime>,
+    >,
     frame_metadata_hash_extension::CheckMetadataHash<Runtime>,
 );
 pub type UncheckedExtrinsic =
@@ -125,6 +163,12 @@ pub fn native_version() -> NativeVersion {
     }
 }
 
+// Migrações pendentes executadas na próxima atualização da runtime
+type Migrations = (
+    pallet_collectables::migrations::v1::MigrateV0ToV1<Runtime>,
+    pallet_collectables::migrations::v2::MigrateV1ToV2<Runtime>,
+);
+
 // Configuração da executive
 pub type Executive = frame_executive::Executive<
     Runtime,
@@ -132,7 +176,7 @@ pub type Executive = frame_executive::Executive<
     frame_system::ChainContext<Runtime>,
     Runtime,
     AllPalletsWithSystem,
-    (),
+    Migrations,
 >;
 
 // Composição da runtime
@@ -175,4 +219,61 @@ mod runtime {
 
     #[runtime::pallet_index(7)]
     pub type Collectables = pallet_collectables;
+
+    #[runtime::pallet_index(8)]
+    pub type Assets = pallet_assets;
+
+    #[runtime::pallet_index(9)]
+    pub type TxPause = pallet_tx_pause;
+
+    #[runtime::pallet_index(10)]
+    pub type Council = pallet_collective<Instance1>;
+
+    #[runtime::pallet_index(11)]
+    pub type Democracy = pallet_democracy;
+
+    #[runtime::pallet_index(12)]
+    pub type Scheduler = pallet_scheduler;
+
+    #[runtime::pallet_index(13)]
+    pub type Preimage = pallet_preimage;
+
+    #[runtime::pallet_index(14)]
+    pub type ValidatorSet = pallet_validator_set;
+
+    #[runtime::pallet_index(15)]
+    pub type Session = pallet_session;
+
+    #[runtime::pallet_index(16)]
+    pub type Historical = pallet_session::historical;
+
+    #[runtime::pallet_index(17)]
+    pub type Offences = pallet_offences;
+
+    #[runtime::pallet_index(18)]
+    pub type Authorship = pallet_authorship;
+
+    #[runtime::pallet_index(19)]
+    pub type Treasury = pallet_treasury;
+
+    #[runtime::pallet_index(20)]
+    pub type Utility = pallet_utility;
+
+    #[runtime::pallet_index(21)]
+    pub type Multisig = pallet_multisig;
+
+    #[runtime::pallet_index(22)]
+    pub type Proxy = pallet_proxy;
+
+    #[runtime::pallet_index(23)]
+    pub type RandomnessCollectiveFlip = pallet_insecure_randomness_collective_flip;
+
+    #[runtime::pallet_index(24)]
+    pub type Contracts = pallet_contracts;
+
+    #[runtime::pallet_index(25)]
+    pub type AssetTxPayment = pallet_asset_tx_payment;
+
+    #[runtime::pallet_index(26)]
+    pub type Vesting = pallet_vesting;
 }